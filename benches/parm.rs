@@ -0,0 +1,46 @@
+//! Compares `parm::expand` with expanding a compiled `parm::Program`, and with expanding into
+//! a reused buffer.
+//!
+//! Run with `cargo bench`.
+
+extern crate terminfo;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use terminfo::{StringCap, Terminfo};
+use terminfo::parm::{expand, expand_to, Param, Program, Variables};
+
+const ITERATIONS: u32 = 200_000;
+
+fn bench<T, F: FnMut() -> T>(name: &str, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+    println!("{:<24} {:>8.1} ns/iter", name, elapsed.as_nanos() as f64 / ITERATIONS as f64);
+}
+
+fn main() {
+    let info = Terminfo::from_path("tests/data/xterm-256color").unwrap();
+    let mut vars = Variables::new();
+    let mut buf = Vec::new();
+    for &cap in &[StringCap::CursorAddress, StringCap::SetAForeground] {
+        let string = info.get(cap).unwrap();
+        let program = Program::compile(string).unwrap();
+        let params = [Param::Number(42), Param::Number(117)];
+        bench(&format!("expand {}", cap), || expand(string, &params, &mut vars).unwrap());
+        bench(&format!("Program::expand {}", cap), || program.expand(&params, &mut vars).unwrap());
+        bench(&format!("expand_to {}", cap), || {
+            buf.clear();
+            expand_to(string, &params, &mut vars, &mut buf).unwrap();
+            buf.len()
+        });
+        bench(&format!("Program::expand_to {}", cap), || {
+            buf.clear();
+            program.expand_to(&params, &mut vars, &mut buf).unwrap();
+            buf.len()
+        });
+    }
+}
@@ -0,0 +1,83 @@
+//! Expands arbitrary capabilities with `parm::expand` and a compiled `parm::Program`, which
+//! must not panic and must agree, and as termcap capabilities.
+
+#![no_main]
+
+use std::io::{self, Write};
+
+use libfuzzer_sys::fuzz_target;
+use terminfo::parm::{analyze, expand_termcap_to, expand_to, Error, Param, Program, Variables};
+
+/// Huge widths can make an expansion arbitrarily long, so give up after this many bytes.
+const MAX_OUTPUT: usize = 1 << 16;
+
+struct Limited(Vec<u8>);
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0.len() + buf.len() > MAX_OUTPUT {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "output too long"));
+        }
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The parameters are taken from the start of the input: a byte telling which ones are
+/// strings, then up to nine numbers of four bytes each, after a `\0`-terminated capability.
+fn split(data: &[u8]) -> Option<(&[u8], Vec<Param>)> {
+    let (&kinds, rest) = data.split_first()?;
+    let end = rest.iter().position(|&c| c == 0).unwrap_or(rest.len());
+    let (cap, numbers) = (&rest[..end], rest.get(end + 1..).unwrap_or(&[]));
+    let params = numbers.chunks_exact(4)
+                        .take(9)
+                        .enumerate()
+                        .map(|(i, n)| {
+                            let n = i32::from_le_bytes([n[0], n[1], n[2], n[3]]);
+                            if kinds & (1 << (i % 8)) != 0 {
+                                Param::Words(n.to_string())
+                            } else {
+                                Param::Number(n)
+                            }
+                        })
+                        .collect();
+    Some((cap, params))
+}
+
+fn run<F>(f: F) -> (Vec<u8>, Result<(), String>)
+    where F: FnOnce(&mut Limited) -> io::Result<()>
+{
+    let mut out = Limited(Vec::new());
+    let result = f(&mut out).map_err(|e| e.to_string());
+    (out.0, result)
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (cap, params) = match split(data) {
+        Some(split) => split,
+        None => return,
+    };
+    let _ = run(|out| expand_termcap_to(cap, &params, &mut Variables::new(), out));
+    let analysis = analyze(cap);
+    let expanded = run(|out| expand_to(cap, &params, &mut Variables::new(), out));
+    match Program::compile(cap) {
+        Ok(program) => {
+            let compiled = run(|out| program.expand_to(&params, &mut Variables::new(), out));
+            assert_eq!(expanded, compiled);
+        }
+        // Expanding runs into the error, unless another one comes before it.
+        Err(_) => assert!(expanded.1.is_err()),
+    }
+    // The analysis finds every error but type mismatches, which may depend on the parameters
+    // and variables.
+    if let (_, Err(ref e)) = expanded {
+        assert!(analysis.errors.iter().any(|a| a.to_string() == *e) ||
+                *e == Error::TypeMismatch.to_string() || e == "output too long",
+                "{}",
+                e);
+    }
+});
@@ -0,0 +1,38 @@
+//! Entries compiled into the crate, for systems without a terminfo database
+//!
+//! These are enabled by the `builtin` feature. `Terminfo::from_name` falls back to them. The
+//! entries were compiled with `tic -x` from the `terminfo.src` of ncurses 6.4 (20221231), without
+//! distribution patches.
+
+use {Source, Terminfo};
+use parser::compiled::TerminfoRef;
+
+static ENTRIES: &[(&str, &[u8])] = &[
+    ("ansi", include_bytes!("builtin/ansi")),
+    ("dumb", include_bytes!("builtin/dumb")),
+    ("linux", include_bytes!("builtin/linux")),
+    ("rxvt-unicode-256color", include_bytes!("builtin/rxvt-unicode-256color")),
+    ("screen", include_bytes!("builtin/screen")),
+    ("screen-256color", include_bytes!("builtin/screen-256color")),
+    ("tmux-256color", include_bytes!("builtin/tmux-256color")),
+    ("vt100", include_bytes!("builtin/vt100")),
+    ("xterm", include_bytes!("builtin/xterm")),
+    ("xterm-256color", include_bytes!("builtin/xterm-256color")),
+];
+
+/// The primary names of the builtin entries.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    ENTRIES.iter().map(|&(name, _)| name)
+}
+
+/// Look up a builtin entry by any of its names.
+pub fn entry(name: &str) -> Option<Terminfo> {
+    ENTRIES.iter()
+           .filter_map(|&(_, data)| TerminfoRef::parse(data).ok())
+           .find(|entry| entry.names().any(|n| n == name))
+           .map(|entry| {
+               let mut info = entry.to_terminfo();
+               info.source = Source::Builtin;
+               info
+           })
+}
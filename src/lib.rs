@@ -10,36 +10,147 @@
 
 //! Terminfo database interface.
 
-use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::prelude::*;
 use std::io;
 use std::io::BufReader;
-use std::path::Path;
+use std::mem;
+use std::path::{Path, PathBuf};
 
-use self::searcher::get_dbpath_for_term;
-use self::parser::compiled::parse;
+use self::searcher::Searcher;
+use self::parser::compiled::{boolnames, numnames, parse, stringnames, unknown_index};
 
+pub use self::parser::compiled::{BoolCap, Category, NumberCap, StringCap, TerminfoRef};
+
+
+/// The location of a string value in `Terminfo::table`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) start: u32,
+    pub(crate) end: u32,
+}
+
+/// The state of a string capability that isn't absent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StringValue {
+    /// A value, possibly empty.
+    Value(Span),
+    /// Cancelled (`name@`), which ncurses treats as absent.
+    Cancelled,
+}
+
+/// Where an entry was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Created with `Terminfo::new`, or parsed from a reader or buffer.
+    Memory,
+    /// A compiled entry file.
+    File(PathBuf),
+    /// A Berkeley DB hashed database.
+    HashedDatabase(PathBuf),
+    /// A termcap file.
+    Termcap(PathBuf),
+    /// One of the entries compiled into the crate by the `builtin` feature.
+    Builtin,
+}
+
+/// How many unused bytes the string table may hold beyond the ones in use before `set_string`
+/// rebuilds it.
+const COMPACT_SLACK: usize = 4096;
+
+// The standard booleans are stored as a bitset.
+const _: () = assert!(BoolCap::ALL.len() <= 64);
 
 /// A parsed terminfo database entry.
-#[derive(Debug, Clone)]
+///
+/// Standard capabilities are stored at their index in `boolnames`/`numnames`/`stringnames`, and
+/// are read with `get`. Extended (user-defined) capabilities are kept sorted by name; they, and
+/// the standard ones, can also be looked up by name with `bool`, `number` and `string`. All string
+/// values share one table.
+///
+/// Standard capabilities past the ones this crate knows, from an entry written by a newer
+/// ncurses, are kept by index so that they are written back to the same place.
+#[derive(Clone)]
 pub struct Terminfo {
     /// Names for the terminal
     pub names: Vec<String>,
-    /// Map of capability name to boolean value
-    pub bools: HashMap<&'static str, bool>,
-    /// Map of capability name to numeric value
-    pub numbers: HashMap<&'static str, u16>,
-    /// Map of capability name to raw (unexpanded) string
-    pub strings: HashMap<&'static str, Vec<u8>>,
+    /// Standard booleans, one bit per index.
+    pub(crate) bools: u64,
+    /// Standard numbers, by index.
+    pub(crate) numbers: Vec<Option<u32>>,
+    /// Standard strings, by index.
+    pub(crate) strings: Vec<Option<StringValue>>,
+    /// Names of the extended booleans that are set.
+    pub(crate) ext_bools: Vec<String>,
+    pub(crate) ext_numbers: Vec<(String, u32)>,
+    pub(crate) ext_strings: Vec<(String, StringValue)>,
+    /// Indices of the unknown standard booleans that are set, sorted.
+    pub(crate) unknown_bools: Vec<usize>,
+    /// Unknown standard numbers, sorted by index.
+    pub(crate) unknown_numbers: Vec<(usize, u32)>,
+    /// Unknown standard strings, sorted by index.
+    pub(crate) unknown_strings: Vec<(usize, StringValue)>,
+    /// The raw (unexpanded) string values.
+    pub(crate) table: Vec<u8>,
+    pub(crate) source: Source,
+}
+
+fn find_extended<T>(entries: &[(String, T)], name: &str) -> Result<usize, usize> {
+    entries.binary_search_by(|entry| entry.0[..].cmp(name))
+}
+
+/// Look up an unknown standard capability by index.
+fn find_unknown<T: Copy>(entries: &[(usize, T)], i: usize) -> Option<T> {
+    entries.binary_search_by_key(&i, |entry| entry.0).ok().map(|j| entries[j].1)
+}
+
+fn set_extended<T>(entries: &mut Vec<(String, T)>, name: &str, value: Option<T>) {
+    match (find_extended(entries, name), value) {
+        (Ok(i), Some(value)) => entries[i].1 = value,
+        (Ok(i), None) => {
+            entries.remove(i);
+        }
+        (Err(i), Some(value)) => entries.insert(i, (name.to_owned(), value)),
+        (Err(_), None) => (),
+    }
 }
 
 impl Terminfo {
-    /// Create a Terminfo for the named terminal.
+    /// Create an entry with the given names and no capabilities.
+    pub fn new(names: Vec<String>) -> Terminfo {
+        Terminfo {
+            names,
+            bools: 0,
+            numbers: vec![None; NumberCap::ALL.len()],
+            strings: vec![None; StringCap::ALL.len()],
+            ext_bools: Vec::new(),
+            ext_numbers: Vec::new(),
+            ext_strings: Vec::new(),
+            unknown_bools: Vec::new(),
+            unknown_numbers: Vec::new(),
+            unknown_strings: Vec::new(),
+            table: Vec::new(),
+            source: Source::Memory,
+        }
+    }
+
+    /// Create a Terminfo for the named terminal, by any of its names that has an entry file (or
+    /// hashed database record); see `searcher::Searcher::entry`.
+    ///
+    /// The search locations are taken from the environment; use `searcher::Searcher` to supply
+    /// them, or to find a terminal by a name that is only listed in its entry. With the `builtin`
+    /// feature, terminals not found there are looked up among the entries compiled into the
+    /// crate.
     pub fn from_name(name: &str) -> io::Result<Terminfo> {
-        get_dbpath_for_term(name)
-            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "database not found"))
-            .and_then(|p| Terminfo::from_path(&p))
+        Searcher::from_env().entry(name)
+    }
+
+    /// Create a Terminfo for the terminal named by `$TERM`, falling back to simpler names (see
+    /// `searcher::fallbacks`).
+    ///
+    /// Returns the name that was found along with the entry.
+    pub fn from_env() -> io::Result<(String, Terminfo)> {
+        Searcher::from_env().term_entry()
     }
 
     /// Parse the given Terminfo.
@@ -53,85 +164,498 @@ impl Terminfo {
     // might do this for
     // us. Alas. )
     fn _from_path(path: &Path) -> io::Result<Terminfo> {
-        let file = try!(File::open(path));
+        let file = File::open(path)?;
         let mut reader = BufReader::new(file);
-        parse(&mut reader)
+        let mut info = parse(&mut reader)?;
+        info.source = Source::File(path.to_path_buf());
+        Ok(info)
+    }
+
+    /// Where the entry was read from.
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Get the value of a standard capability: a `bool` for `BoolCap`, an `Option<u32>` for
+    /// `NumberCap` and an `Option<&[u8]>` for `StringCap`.
+    ///
+    /// Cancelled strings are reported as absent. Extended capabilities can be looked up by name
+    /// with `bool`, `number` and `string`.
+    pub fn get<C: Capability>(&self, cap: C) -> C::Value<'_> {
+        cap.get(self)
+    }
+
+    /// Check whether a standard capability is present. Booleans are present when set.
+    pub fn contains<C: Capability>(&self, cap: C) -> bool {
+        cap.is_present(self)
+    }
+
+    /// Look up a boolean capability, standard or extended, by name.
+    ///
+    /// Unknown standard capabilities are named after their index, like `bool44`.
+    pub fn bool(&self, name: &str) -> bool {
+        match BoolCap::from_name(name) {
+            Some(cap) => self.bools & 1 << cap.index() != 0,
+            None => {
+                self.ext_bools.binary_search_by(|n| n[..].cmp(name)).is_ok() ||
+                unknown_index(boolnames, "bool", name).is_some_and(|i| {
+                    self.unknown_bools.binary_search(&i).is_ok()
+                })
+            }
+        }
+    }
+
+    /// Look up a numeric capability, standard or extended, by name.
+    ///
+    /// Unknown standard capabilities are named after their index, like `num39`.
+    pub fn number(&self, name: &str) -> Option<u32> {
+        match NumberCap::from_name(name) {
+            Some(cap) => self.numbers[cap.index()],
+            None => {
+                find_extended(&self.ext_numbers, name)
+                    .ok()
+                    .map(|i| self.ext_numbers[i].1)
+                    .or_else(|| {
+                        unknown_index(numnames, "num", name)
+                            .and_then(|i| find_unknown(&self.unknown_numbers, i))
+                    })
+            }
+        }
+    }
+
+    /// Look up the raw (unexpanded) value of a string capability, standard or extended, by name.
+    ///
+    /// Cancelled strings are reported as absent. Unknown standard capabilities are named after
+    /// their index, like `str414`.
+    pub fn string(&self, name: &str) -> Option<&[u8]> {
+        let value = match StringCap::from_name(name) {
+            Some(cap) => self.strings[cap.index()],
+            None => {
+                find_extended(&self.ext_strings, name)
+                    .ok()
+                    .map(|i| self.ext_strings[i].1)
+                    .or_else(|| {
+                        unknown_index(stringnames, "str", name)
+                            .and_then(|i| find_unknown(&self.unknown_strings, i))
+                    })
+            }
+        };
+        value.and_then(|value| self.value(value))
+    }
+
+    /// Set or unset a boolean capability by name.
+    ///
+    /// Names that aren't in `boolnames` are stored as extended capabilities.
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        match BoolCap::from_name(name) {
+            Some(cap) if value => self.bools |= 1 << cap.index(),
+            Some(cap) => self.bools &= !(1 << cap.index()),
+            None => {
+                match (self.ext_bools.binary_search_by(|n| n[..].cmp(name)), value) {
+                    (Err(i), true) => self.ext_bools.insert(i, name.to_owned()),
+                    (Ok(i), false) => {
+                        self.ext_bools.remove(i);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Set or remove a numeric capability by name.
+    ///
+    /// Names that aren't in `numnames` are stored as extended capabilities.
+    pub fn set_number(&mut self, name: &str, value: Option<u32>) {
+        match NumberCap::from_name(name) {
+            Some(cap) => self.numbers[cap.index()] = value,
+            None => set_extended(&mut self.ext_numbers, name, value),
+        }
+    }
+
+    /// Set or remove a string capability by name.
+    ///
+    /// Names that aren't in `stringnames` are stored as extended capabilities.
+    ///
+    /// New values are appended to the shared string table. Replaced and removed values are left
+    /// in it until they make up most of the table, at which point it is rebuilt.
+    pub fn set_string(&mut self, name: &str, value: Option<&[u8]>) {
+        let value = value.map(|s| StringValue::Value(self.push_string(s)));
+        self.set_value(name, value);
+
+        let live: usize = self.spans().map(|span| (span.end - span.start) as usize).sum();
+        if self.table.len() > 2 * live + COMPACT_SLACK {
+            self.compact();
+        }
+    }
+
+    /// Cancel a string capability by name, as `name@` does in terminfo source.
+    ///
+    /// A cancelled capability is reported as absent, but is written back as cancelled.
+    pub fn cancel_string(&mut self, name: &str) {
+        self.set_value(name, Some(StringValue::Cancelled));
+    }
+
+    /// Names of the boolean capabilities that are set, standard ones first.
+    ///
+    /// Standard capabilities unknown to this crate are left out; see `unknown_bools`.
+    pub fn bools(&self) -> impl Iterator<Item = &str> + '_ {
+        BoolCap::ALL.iter()
+                    .filter(move |cap| self.bools & 1 << cap.index() != 0)
+                    .map(|cap| cap.name())
+                    .chain(self.ext_bools.iter().map(|n| &n[..]))
+    }
+
+    /// The numeric capabilities, standard ones first.
+    ///
+    /// Standard capabilities unknown to this crate are left out; see `unknown_numbers`.
+    pub fn numbers(&self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        NumberCap::ALL.iter()
+                      .zip(&self.numbers)
+                      .filter_map(|(cap, n)| n.map(|n| (cap.name(), n)))
+                      .chain(self.ext_numbers.iter().map(|&(ref name, n)| (&name[..], n)))
+    }
+
+    /// The raw string capabilities, standard ones first.
+    ///
+    /// Cancelled strings and standard capabilities unknown to this crate are left out; see
+    /// `cancelled_strings` and `unknown_strings`.
+    pub fn strings(&self) -> impl Iterator<Item = (&str, &[u8])> + '_ {
+        self.string_values()
+            .filter_map(move |(name, value)| self.value(value).map(|s| (name, s)))
+    }
+
+    /// Names of the string capabilities that are cancelled, standard ones first.
+    pub fn cancelled_strings(&self) -> impl Iterator<Item = &str> + '_ {
+        self.string_values()
+            .filter(|&(_, value)| value == StringValue::Cancelled)
+            .map(|(name, _)| name)
+    }
+
+    /// The string capabilities that are set or cancelled, standard ones first.
+    pub(crate) fn string_values(&self) -> impl Iterator<Item = (&str, StringValue)> + '_ {
+        StringCap::ALL.iter()
+                      .zip(&self.strings)
+                      .filter_map(|(cap, value)| value.map(|value| (cap.name(), value)))
+                      .chain(self.ext_strings.iter().map(|&(ref name, value)| (&name[..], value)))
+    }
+
+    /// The indices of the standard booleans that are set but unknown to this crate, because
+    /// they come after `boolnames` in an entry from a newer ncurses.
+    ///
+    /// These aren't yielded by `bools`, but can be looked up with `bool` by names like `bool44`.
+    pub fn unknown_bools(&self) -> impl Iterator<Item = usize> + '_ {
+        self.unknown_bools.iter().cloned()
+    }
+
+    /// The standard numeric capabilities unknown to this crate, by index; see `unknown_bools`.
+    pub fn unknown_numbers(&self) -> impl Iterator<Item = (usize, u32)> + '_ {
+        self.unknown_numbers.iter().cloned()
+    }
+
+    /// The standard raw string capabilities unknown to this crate, by index; see
+    /// `unknown_bools`.
+    pub fn unknown_strings(&self) -> impl Iterator<Item = (usize, &[u8])> + '_ {
+        self.unknown_strings.iter().filter_map(move |&(i, value)| self.value(value).map(|s| (i, s)))
+    }
+
+    /// The standard capabilities that are present, as their terminfo name, long name,
+    /// description and value: booleans first, then numbers, then raw strings. Cancelled strings
+    /// are left out.
+    ///
+    /// Use `parser::compiled::category` to group them.
+    pub fn describe(&self) -> impl Iterator<Item = Description<'_>> + '_ {
+        let bools = BoolCap::ALL.iter()
+                                .filter(move |&&cap| self.contains(cap))
+                                .map(|&cap| {
+                                    (cap.name(), cap.long_name(), cap.description(), Value::Bool)
+                                });
+        let numbers = NumberCap::ALL.iter().zip(&self.numbers).filter_map(|(&cap, n)| {
+            n.map(|n| (cap.name(), cap.long_name(), cap.description(), Value::Number(n)))
+        });
+        let strings = StringCap::ALL.iter().zip(&self.strings).filter_map(move |(&cap, value)| {
+            value.and_then(|value| self.value(value)).map(|s| {
+                (cap.name(), cap.long_name(), cap.description(), Value::String(s))
+            })
+        });
+        bools.chain(numbers).chain(strings)
+    }
+
+    pub(crate) fn span(&self, span: Span) -> &[u8] {
+        &self.table[span.start as usize..span.end as usize]
+    }
+
+    /// The raw value of a string capability, or `None` if it is cancelled.
+    pub(crate) fn value(&self, value: StringValue) -> Option<&[u8]> {
+        match value {
+            StringValue::Value(span) => Some(self.span(span)),
+            StringValue::Cancelled => None,
+        }
+    }
+
+    /// Append a string value to the table.
+    pub(crate) fn push_string(&mut self, s: &[u8]) -> Span {
+        let start = self.table.len() as u32;
+        self.table.extend_from_slice(s);
+        Span {
+            start,
+            end: self.table.len() as u32,
+        }
+    }
+
+    /// Set, cancel or remove a string capability whose value is already in the table.
+    pub(crate) fn set_value(&mut self, name: &str, value: Option<StringValue>) {
+        match StringCap::from_name(name) {
+            Some(cap) => self.strings[cap.index()] = value,
+            None => set_extended(&mut self.ext_strings, name, value),
+        }
+    }
+
+    /// The spans of all the string values, standard and extended.
+    fn spans(&mut self) -> impl Iterator<Item = &mut Span> {
+        self.strings
+            .iter_mut()
+            .flatten()
+            .chain(self.ext_strings.iter_mut().map(|entry| &mut entry.1))
+            .chain(self.unknown_strings.iter_mut().map(|entry| &mut entry.1))
+            .filter_map(|value| {
+                match *value {
+                    StringValue::Value(ref mut span) => Some(span),
+                    StringValue::Cancelled => None,
+                }
+            })
+    }
+
+    /// Rebuild the string table with only the values that are still in use.
+    fn compact(&mut self) {
+        let old = mem::take(&mut self.table);
+        let mut table = Vec::with_capacity(old.len() / 2);
+        for span in self.spans() {
+            let start = table.len() as u32;
+            table.extend_from_slice(&old[span.start as usize..span.end as usize]);
+            *span = Span {
+                start,
+                end: table.len() as u32,
+            };
+        }
+        self.table = table;
+    }
+}
+
+impl PartialEq for Terminfo {
+    fn eq(&self, other: &Terminfo) -> bool {
+        // The string tables may differ in layout.
+        self.names == other.names && self.bools == other.bools &&
+        self.numbers == other.numbers && self.ext_bools == other.ext_bools &&
+        self.ext_numbers == other.ext_numbers && self.unknown_bools == other.unknown_bools &&
+        self.unknown_numbers == other.unknown_numbers && self.strings().eq(other.strings()) &&
+        self.cancelled_strings().eq(other.cancelled_strings()) &&
+        self.unknown_strings.iter()
+            .map(|&(i, value)| (i, self.value(value)))
+            .eq(other.unknown_strings.iter().map(|&(i, value)| (i, other.value(value))))
+    }
+}
+
+impl Eq for Terminfo {}
+
+impl fmt::Debug for Terminfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Terminfo");
+        s.field("names", &self.names)
+         .field("bools", &self.bools().collect::<Vec<_>>())
+         .field("numbers", &self.numbers().collect::<Vec<_>>())
+         .field("strings", &self.strings().collect::<Vec<_>>());
+        if self.cancelled_strings().next().is_some() {
+            s.field("cancelled_strings", &self.cancelled_strings().collect::<Vec<_>>());
+        }
+        if !self.unknown_bools.is_empty() || !self.unknown_numbers.is_empty() ||
+           !self.unknown_strings.is_empty() {
+            s.field("unknown_bools", &self.unknown_bools)
+             .field("unknown_numbers", &self.unknown_numbers)
+             .field("unknown_strings", &self.unknown_strings().collect::<Vec<_>>());
+        }
+        s.finish()
+    }
+}
+
+/// The value of a capability that is present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value<'a> {
+    /// A boolean, which is present only when set.
+    Bool,
+    /// A number.
+    Number(u32),
+    /// The raw (unexpanded) string.
+    String(&'a [u8]),
+}
+
+/// A standard capability as yielded by `Terminfo::describe`: its terminfo name, long name,
+/// description and value.
+pub type Description<'a> = (&'static str, &'static str, &'static str, Value<'a>);
+
+/// A typed key for a standard capability: `BoolCap`, `NumberCap` or `StringCap`.
+pub trait Capability: Copy {
+    /// The type returned by `Terminfo::get`.
+    type Value<'a>;
+
+    /// Get the value of this capability in `info`; see `Terminfo::get`.
+    fn get(self, info: &Terminfo) -> Self::Value<'_>;
+
+    /// Check whether this capability is present in `info`; see `Terminfo::contains`.
+    fn is_present(self, info: &Terminfo) -> bool;
+}
+
+impl Capability for BoolCap {
+    type Value<'a> = bool;
+
+    fn get(self, info: &Terminfo) -> bool {
+        info.bools & 1 << self.index() != 0
+    }
+
+    fn is_present(self, info: &Terminfo) -> bool {
+        self.get(info)
     }
 }
 
-#[derive(Debug)]
-/// An error from parsing a terminfo entry
-pub enum Error {
+impl Capability for NumberCap {
+    type Value<'a> = Option<u32>;
+
+    fn get(self, info: &Terminfo) -> Option<u32> {
+        info.numbers[self.index()]
+    }
+
+    fn is_present(self, info: &Terminfo) -> bool {
+        self.get(info).is_some()
+    }
+}
+
+impl Capability for StringCap {
+    type Value<'a> = Option<&'a [u8]>;
+
+    fn get(self, info: &Terminfo) -> Option<&[u8]> {
+        info.strings[self.index()].and_then(|value| info.value(value))
+    }
+
+    fn is_present(self, info: &Terminfo) -> bool {
+        self.get(info).is_some()
+    }
+}
+
+/// An error from parsing a compiled terminfo entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The byte offset where the error was found, counted from the start of the entry rather
+    /// than of `section`.
+    pub offset: usize,
+    /// The section of the entry the error was found in.
+    pub section: Section,
+    /// What went wrong.
+    pub kind: ErrorKind,
+}
+
+/// The sections of a compiled terminfo entry, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// The magic number and the section sizes.
+    Header,
+    /// The `|`-separated terminal names.
+    Names,
+    /// The standard boolean capabilities, one byte each.
+    Bools,
+    /// The standard numeric capabilities.
+    Numbers,
+    /// The offsets of the standard string capabilities in the string table.
+    StringOffsets,
+    /// The values of the standard string capabilities.
+    StringTable,
+    /// The extended (user-defined) capabilities, including their own header.
+    Extended,
+}
+
+/// The kind of error encountered while parsing a compiled terminfo entry.
+///
+/// The `Error` it is part of locates it by its absolute offset in the entry, and by section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The entry ended in the middle of a section.
+    UnexpectedEof,
     /// The "magic" number at the start of the file was wrong.
     ///
-    /// It should be `0x11A`
+    /// It should be `0x11A` (legacy 16-bit numbers) or `0x21E` (32-bit numbers).
     BadMagic(u16),
+    /// The header field with the given name, a count or a size, was not >= -1.
+    InvalidLength(&'static str),
+    /// The names section of the file was empty
+    ShortNames,
+    /// The names table was missing a trailing null terminator.
+    NamesMissingNull,
     /// The names in the file were not valid UTF-8.
     ///
     /// In theory these should only be ASCII, but to work with the Rust `str` type, we treat them
     /// as UTF-8. This is valid, except when a terminfo file decides to be invalid. This hasn't
     /// been encountered in the wild.
     NotUtf8(::std::str::Utf8Error),
-    /// The names section of the file was empty
-    ShortNames,
-    /// More boolean parameters are present in the file than this crate knows how to interpret.
-    TooManyBools,
-    /// More number parameters are present in the file than this crate knows how to interpret.
-    TooManyNumbers,
-    /// More string parameters are present in the file than this crate knows how to interpret.
-    TooManyStrings,
-    /// The length of some field was not >= -1.
-    InvalidLength,
-    /// The names table was missing a trailing null terminator.
-    NamesMissingNull,
-    /// The strings table was missing a trailing null terminator.
-    StringsMissingNull,
+    /// The value of the named string capability starts outside the string table.
+    InvalidStringOffset(String),
+    /// The value of the named string capability runs past the end of the string table.
+    StringsMissingNull(String),
+    /// The name of the extended capability with the given index (counting booleans, then
+    /// numbers, then strings) is outside the string table or not NUL-terminated.
+    InvalidName(usize),
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Section::Header => "header",
+            Section::Names => "names",
+            Section::Bools => "booleans",
+            Section::Numbers => "numbers",
+            Section::StringOffsets => "string offsets",
+            Section::StringTable => "string table",
+            Section::Extended => "extended capabilities",
+        })
+    }
 }
 
-impl ::std::fmt::Display for Error {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        use std::error::Error;
-        use Error::*;
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ErrorKind::*;
         match *self {
+            UnexpectedEof => f.write_str("unexpected end of entry"),
             NotUtf8(e) => write!(f, "{}", e),
             BadMagic(v) => write!(f, "bad magic number {:x} in terminfo header", v),
-            _ => f.write_str(self.description()),
+            ShortNames => f.write_str("no names exposed, need at least one"),
+            InvalidLength(field) => write!(f, "invalid {}, must be >= -1", field),
+            NamesMissingNull => f.write_str("names table missing NUL terminator"),
+            InvalidStringOffset(ref name) => write!(f, "value of {} outside string table", name),
+            StringsMissingNull(ref name) => {
+                write!(f, "value of {} missing NUL terminator in string table", name)
+            }
+            InvalidName(i) => write!(f, "invalid name of extended capability {}", i),
         }
     }
 }
 
-impl ::std::convert::From<::std::string::FromUtf8Error> for Error {
-    fn from(v: ::std::string::FromUtf8Error) -> Self {
-        Error::NotUtf8(v.utf8_error())
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}: {}", self.section, self.offset, self.kind)
     }
 }
 
 impl ::std::convert::From<Error> for io::Error {
     fn from(e: Error) -> Self {
-        io::Error::new(io::ErrorKind::InvalidData, e)
+        let kind = match e.kind {
+            ErrorKind::UnexpectedEof => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
     }
 }
 
 impl ::std::error::Error for Error {
-    fn description(&self) -> &str {
-        use Error::*;
-        match *self {
-            BadMagic(..) => "incorrect magic number at start of file",
-            ShortNames => "no names exposed, need at least one",
-            TooManyBools => "more boolean properties than libterm knows about",
-            TooManyNumbers => "more number properties than libterm knows about",
-            TooManyStrings => "more string properties than libterm knows about",
-            InvalidLength => "invalid length field value, must be >= -1",
-            NotUtf8(ref e) => e.description(),
-            NamesMissingNull => "names table missing NUL terminator",
-            StringsMissingNull => "string table missing NUL terminator",
-        }
-    }
-
-    fn cause(&self) -> Option<&::std::error::Error> {
-        use Error::*;
-        match *self {
-            NotUtf8(ref e) => Some(e),
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            ErrorKind::NotUtf8(ref e) => Some(e),
             _ => None,
         }
     }
@@ -141,8 +665,43 @@ pub mod searcher;
 
 /// Terminfo format parsing.
 pub mod parser {
-    //! ncurses-compatible compiled terminfo format parsing (term(5))
     pub mod compiled;
+    pub mod hashed;
+    pub mod source;
+    pub mod termcap;
     mod names;
 }
+
+/// Terminfo format writing.
+pub mod writer {
+    pub mod compiled;
+    pub mod source;
+}
 pub mod parm;
+pub mod padding;
+#[cfg(feature = "builtin")]
+pub mod builtin;
+
+#[cfg(test)]
+mod test {
+    use super::{Terminfo, COMPACT_SLACK};
+
+    #[test]
+    fn test_set_string_compacts() {
+        let mut info = Terminfo::new(vec!["test".to_owned()]);
+        info.set_string("bel", Some(b"\x07"));
+        info.set_string("Ss", Some(b"\x1b[%p1%d q"));
+        for i in 0..10000 {
+            let value = format!("\x1b[{}H", i);
+            info.set_string("home", Some(value.as_bytes()));
+            info.set_string("Se", Some(value.as_bytes()));
+            info.set_string("clear", if i % 2 == 0 { Some(b"\x1b[2J") } else { None });
+        }
+        assert!(info.table.len() < 3 * COMPACT_SLACK);
+        assert_eq!(info.string("bel"), Some(&b"\x07"[..]));
+        assert_eq!(info.string("Ss"), Some(&b"\x1b[%p1%d q"[..]));
+        assert_eq!(info.string("home"), Some(&b"\x1b[9999H"[..]));
+        assert_eq!(info.string("Se"), Some(&b"\x1b[9999H"[..]));
+        assert_eq!(info.string("clear"), None);
+    }
+}
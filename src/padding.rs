@@ -0,0 +1,205 @@
+//! Padding of expanded capabilities, like `tputs`
+//!
+//! Capabilities may contain delays of the form `$<n>`, where `n` is a number of milliseconds
+//! with at most one decimal place. A `*` after the number makes the delay proportional to the
+//! number of lines affected, and a `/` makes it mandatory, e.g. `$<5*/>`. Depending on the
+//! output speed and the `pb`, `xon` and `npc` capabilities, a delay is dropped, sent as pad
+//! characters or slept. Delays are cut short at `MAX_DELAY_MS`.
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use {BoolCap, NumberCap, StringCap, Terminfo};
+
+/// The number of bits a byte takes on the line: 8 data bits and a stop bit.
+const BITS_PER_BYTE: u64 = 9;
+
+/// The longest delay carried out, in milliseconds. Longer ones are cut short.
+pub const MAX_DELAY_MS: u64 = 10_000;
+
+/// How to carry out the delays of a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Padding {
+    /// The output speed, in bits per second.
+    pub baud_rate: u32,
+    /// The lowest speed at which delays that aren't mandatory are carried out (`pb`). Without
+    /// one, only mandatory delays are.
+    pub padding_baud_rate: Option<u32>,
+    /// Whether the terminal uses XON/XOFF flow control (`xon`), making delays that aren't
+    /// mandatory unnecessary.
+    pub xon_xoff: bool,
+    /// Whether the terminal has no pad character (`npc`), so that delays have to be slept.
+    pub no_pad_char: bool,
+    /// The pad character (the first byte of `pad`, or NUL).
+    pub pad_char: u8,
+}
+
+impl Padding {
+    /// The padding settings of an entry for the given output speed.
+    pub fn new(info: &Terminfo, baud_rate: u32) -> Padding {
+        Padding {
+            baud_rate,
+            padding_baud_rate: info.get(NumberCap::PaddingBaudRate),
+            xon_xoff: info.get(BoolCap::XonXoff),
+            no_pad_char: info.get(BoolCap::NoPadChar),
+            pad_char: info.get(StringCap::PadChar).and_then(|pad| pad.first()).map_or(0, |&c| c),
+        }
+    }
+
+    /// Write an expanded capability, carrying out its delays.
+    ///
+    /// `affected_lines` is the number of lines the capability affects, which `$<n*>` delays
+    /// are multiplied by. Text that looks like a delay but isn't one is written as is.
+    pub fn write(&self, s: &[u8], affected_lines: u32, out: &mut dyn io::Write) -> io::Result<()> {
+        let normal = !self.xon_xoff &&
+                     self.padding_baud_rate.is_some_and(|pb| pb > 0 && self.baud_rate >= pb);
+        let mut rest = s;
+        while let Some(start) = find_delay(rest) {
+            out.write_all(&rest[..start])?;
+            let (delay, len) = parse_delay(&rest[start..], affected_lines);
+            if delay.tenths > 0 && (normal || delay.mandatory) {
+                self.delay(delay.tenths / 10, out)?;
+            }
+            rest = &rest[start + len..];
+        }
+        out.write_all(rest)
+    }
+
+    /// Delay the output by `ms` milliseconds, at most `MAX_DELAY_MS`.
+    fn delay(&self, ms: u64, out: &mut dyn io::Write) -> io::Result<()> {
+        let ms = ms.min(MAX_DELAY_MS);
+        if self.no_pad_char {
+            out.flush()?;
+            thread::sleep(Duration::from_millis(ms));
+        } else {
+            let mut count = ms * self.baud_rate as u64 / (BITS_PER_BYTE * 1000);
+            let pad = [self.pad_char; 256];
+            while count > 0 {
+                let n = count.min(pad.len() as u64) as usize;
+                out.write_all(&pad[..n])?;
+                count -= n as u64;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed `$<..>` delay.
+#[derive(Debug, PartialEq, Eq)]
+struct Delay {
+    /// The delay in tenths of milliseconds.
+    tenths: u64,
+    mandatory: bool,
+}
+
+/// Find the start of the next delay: a `$<` followed by a digit or a `.`, with a `>` later on.
+fn find_delay(s: &[u8]) -> Option<usize> {
+    let start = s.windows(3).position(|w| w[0] == b'$' && w[1] == b'<' &&
+                                          (w[2].is_ascii_digit() || w[2] == b'.'))?;
+    if s[start..].contains(&b'>') {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+/// Parse the delay at the start of `s`, returning it and its length up to and including the
+/// closing `>`. Like ncurses, digits after the first decimal place are ignored, and so is
+/// anything else before the `>`.
+fn parse_delay(s: &[u8], affected_lines: u32) -> (Delay, usize) {
+    let mut i = 2;
+    let mut tenths: u64 = 0;
+    while let Some(d) = s.get(i).filter(|c| c.is_ascii_digit()) {
+        tenths = tenths.saturating_mul(10).saturating_add((d - b'0') as u64);
+        i += 1;
+    }
+    tenths = tenths.saturating_mul(10);
+    if s.get(i) == Some(&b'.') {
+        i += 1;
+        if let Some(d) = s.get(i).filter(|c| c.is_ascii_digit()) {
+            tenths = tenths.saturating_add((d - b'0') as u64);
+        }
+        while s.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+    }
+    let mut mandatory = false;
+    loop {
+        match s.get(i) {
+            Some(b'*') => tenths = tenths.saturating_mul(affected_lines as u64),
+            Some(b'/') => mandatory = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    let len = i + s[i..].iter().position(|&c| c == b'>').map_or(s.len() - i, |end| end + 1);
+    let tenths = tenths.min(MAX_DELAY_MS * 10);
+    (Delay { tenths, mandatory }, len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_delay, Delay, Padding, MAX_DELAY_MS};
+
+    fn padding(baud_rate: u32) -> Padding {
+        Padding {
+            baud_rate,
+            padding_baud_rate: Some(1200),
+            xon_xoff: false,
+            no_pad_char: false,
+            pad_char: 0,
+        }
+    }
+
+    fn write(padding: Padding, s: &[u8], affected_lines: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        padding.write(s, affected_lines, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_parse_delay() {
+        let delay = |tenths, mandatory| Delay { tenths, mandatory };
+        assert_eq!(parse_delay(b"$<5>x", 1), (delay(50, false), 4));
+        assert_eq!(parse_delay(b"$<2.5*/>", 3), (delay(75, true), 8));
+        assert_eq!(parse_delay(b"$<.75>", 1), (delay(7, false), 6));
+        assert_eq!(parse_delay(b"$<10/*>", 0), (delay(0, true), 7));
+    }
+
+    #[test]
+    fn test_write() {
+        // At 9600 baud, 8ms take 8 bytes; ncurses drops the fraction of a millisecond.
+        assert_eq!(write(padding(9600), b"\x1b[H$<8.4>x", 1), b"\x1b[H\0\0\0\0\0\0\0\0x");
+        assert_eq!(write(padding(9600), b"a$<5*>b", 2), b"a\0\0\0\0\0\0\0\0\0\0b");
+        // Below `pb` only mandatory delays are carried out.
+        assert_eq!(write(padding(300), b"a$<100>b", 1), b"ab");
+        assert_eq!(write(padding(300), b"a$<100/>b", 1), b"a\0\0\0b");
+        assert_eq!(write(Padding { xon_xoff: true, ..padding(9600) }, b"a$<100>b", 1), b"ab");
+        assert_eq!(write(Padding { pad_char: b'*', ..padding(9600) }, b"$<1>", 1), b"*");
+        assert_eq!(write(Padding { no_pad_char: true, ..padding(9600) }, b"a$<1>b", 1), b"ab");
+        // Not delays.
+        assert_eq!(write(padding(9600), b"$$<x>$<5", 1), b"$$<x>$<5");
+    }
+
+    #[test]
+    fn test_huge_delay() {
+        let max = Delay {
+            tenths: MAX_DELAY_MS * 10,
+            mandatory: false,
+        };
+        assert_eq!(parse_delay(b"$<99999999999999999999999>", 1), (max, 26));
+        // At 9600 baud, the longest delay takes 10666 bytes.
+        let out = write(padding(9600), b"a$<99999999999999999999999>b", 1);
+        assert_eq!(out.len(), 10668);
+        assert!(out[1..10667].iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_huge_proportional_delay() {
+        let delay = parse_delay(b"$<99999.9*/>", u32::MAX).0;
+        assert_eq!(delay.tenths, MAX_DELAY_MS * 10);
+        assert!(delay.mandatory);
+        assert_eq!(write(padding(9600), b"$<1000*>", u32::MAX).len(), 10666);
+    }
+}
@@ -14,10 +14,9 @@ use self::Param::*;
 use self::States::*;
 use self::FormatState::*;
 use self::FormatOp::*;
+use std::collections::{HashMap, HashSet};
 use std::io;
 
-use std::iter::repeat;
-
 #[derive(Clone, Copy, PartialEq)]
 enum States {
     Nothing,
@@ -35,6 +34,7 @@ enum States {
     SeekIfEndPercent(usize),
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Copy, PartialEq, Clone)]
 enum FormatState {
     FormatStateFlags,
@@ -52,7 +52,7 @@ pub enum Param {
 
 
 /// An error from interpreting a parameterized string.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Error {
     /// Data was requested from the stack, but the stack didn't have enough elements.
     StackUnderflow,
@@ -75,6 +75,8 @@ pub enum Error {
     FormatWidthOverflow,
     /// A format precision constant was too large (overflowed a usize)
     FormatPrecisionOverflow,
+    /// A termcap capability used more than the nine parameters terminfo has.
+    TooManyParameters,
 }
 
 impl From<Error> for io::Error {
@@ -85,15 +87,8 @@ impl From<Error> for io::Error {
 
 impl ::std::fmt::Display for Error {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        use std::error::Error;
-        f.write_str(self.description())
-    }
-}
-
-impl ::std::error::Error for Error {
-    fn description(&self) -> &str {
         use self::Error::*;
-        match *self {
+        f.write_str(match *self {
             StackUnderflow => "not enough elements on the stack",
             TypeMismatch => "type mismatch",
             UnrecognizedFormatOption(_) => "unrecognized format option",
@@ -104,14 +99,13 @@ impl ::std::error::Error for Error {
             MalformedIntegerConstant => "malformed integer constant",
             FormatWidthOverflow => "format width constant computation overflowed",
             FormatPrecisionOverflow => "format precision constant computation overflowed",
-        }
-    }
-
-    fn cause(&self) -> Option<&::std::error::Error> {
-        None
+            TooManyParameters => "more than nine parameters",
+        })
     }
 }
 
+impl ::std::error::Error for Error {}
+
 /// Container for static and dynamic variable arrays
 pub struct Variables {
     /// Static variables A-Z
@@ -136,6 +130,12 @@ impl Variables {
     }
 }
 
+impl Default for Variables {
+    fn default() -> Variables {
+        Variables::new()
+    }
+}
+
 /// Expand a parameterized capability
 ///
 /// # Arguments
@@ -146,10 +146,67 @@ impl Variables {
 /// To be compatible with ncurses, `vars` should be the same between calls to `expand` for
 /// multiple capabilities for the same terminal.
 pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<u8>, Error> {
-    let mut state = Nothing;
-
     // expanded cap will only rarely be larger than the cap itself
     let mut output = Vec::with_capacity(cap.len());
+    expand_into(cap, params, vars, &mut output).map(|()| output).map_err(Failure::into_error)
+}
+
+/// Expand a parameterized capability into `out`, like `expand`.
+///
+/// Errors from `expand` are reported with the `InvalidData` kind. The output up to the error
+/// may have been written by then.
+pub fn expand_to<W: io::Write + ?Sized>(cap: &[u8],
+                                        params: &[Param],
+                                        vars: &mut Variables,
+                                        out: &mut W)
+                                        -> io::Result<()> {
+    expand_into(cap, params, vars, out).map_err(io::Error::from)
+}
+
+/// The ways expanding into a writer can fail.
+#[derive(Debug)]
+enum Failure {
+    Parm(Error),
+    Io(io::Error),
+}
+
+impl Failure {
+    /// The error of an expansion into a `Vec`, which can't fail to be written to.
+    fn into_error(self) -> Error {
+        match self {
+            Failure::Parm(e) => e,
+            Failure::Io(e) => unreachable!("writing to a Vec failed: {}", e),
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Failure {
+        Failure::Parm(e)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Failure {
+        Failure::Io(e)
+    }
+}
+
+impl From<Failure> for io::Error {
+    fn from(e: Failure) -> io::Error {
+        match e {
+            Failure::Parm(e) => e.into(),
+            Failure::Io(e) => e,
+        }
+    }
+}
+
+fn expand_into<W: io::Write + ?Sized>(cap: &[u8],
+                                      params: &[Param],
+                                      vars: &mut Variables,
+                                      output: &mut W)
+                                      -> Result<(), Failure> {
+    let mut state = Nothing;
 
     let mut stack: Vec<Param> = Vec::new();
 
@@ -160,31 +217,37 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
         *dst = (*src).clone();
     }
 
-    for &c in cap.iter() {
+    // Where the run of literal bytes that hasn't been written yet starts.
+    let mut literal = None;
+
+    for (i, &c) in cap.iter().enumerate() {
         let cur = c as char;
         let mut old_state = state;
         match state {
             Nothing => {
                 if cur == '%' {
+                    if let Some(start) = literal.take() {
+                        output.write_all(&cap[start..i])?;
+                    }
                     state = Percent;
-                } else {
-                    output.push(c);
+                } else if literal.is_none() {
+                    literal = Some(i);
                 }
             }
             Percent => {
                 match cur {
                     '%' => {
-                        output.push(c);
+                        literal = Some(i);
                         state = Nothing
                     }
                     'c' => {
                         match stack.pop() {
                             // if c is 0, use 0200 (128) for ncurses compatibility
-                            Some(Number(0)) => output.push(128u8),
+                            Some(Number(0)) => output.write_all(&[128u8])?,
                             // Don't check bounds. ncurses just casts and truncates.
-                            Some(Number(c)) => output.push(c as u8),
-                            Some(_) => return Err(Error::TypeMismatch),
-                            None => return Err(Error::StackUnderflow),
+                            Some(Number(c)) => output.write_all(&[c as u8])?,
+                            Some(_) => return Err(Error::TypeMismatch.into()),
+                            None => return Err(Error::StackUnderflow.into()),
                         }
                     }
                     'p' => state = PushParam,
@@ -195,70 +258,21 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                     'l' => {
                         match stack.pop() {
                             Some(Words(s)) => stack.push(Number(s.len() as i32)),
-                            Some(_) => return Err(Error::TypeMismatch),
-                            None => return Err(Error::StackUnderflow),
+                            Some(_) => return Err(Error::TypeMismatch.into()),
+                            None => return Err(Error::StackUnderflow.into()),
                         }
                     }
-                    '+' | '-' | '/' | '*' | '^' | '&' | '|' | 'm' => {
-                        match (stack.pop(), stack.pop()) {
-                            (Some(Number(y)), Some(Number(x))) => {
-                                stack.push(Number(match cur {
-                                    '+' => x + y,
-                                    '-' => x - y,
-                                    '*' => x * y,
-                                    '/' => x / y,
-                                    '|' => x | y,
-                                    '&' => x & y,
-                                    '^' => x ^ y,
-                                    'm' => x % y,
-                                    _ => unreachable!("logic error"),
-                                }))
-                            }
-                            (Some(_), Some(_)) => return Err(Error::TypeMismatch),
-                            _ => return Err(Error::StackUnderflow),
-                        }
-                    }
-                    '=' | '>' | '<' | 'A' | 'O' => {
-                        match (stack.pop(), stack.pop()) {
-                            (Some(Number(y)), Some(Number(x))) => {
-                                stack.push(Number(if match cur {
-                                    '=' => x == y,
-                                    '<' => x < y,
-                                    '>' => x > y,
-                                    'A' => x > 0 && y > 0,
-                                    'O' => x > 0 || y > 0,
-                                    _ => unreachable!("logic error"),
-                                } {
-                                    1
-                                } else {
-                                    0
-                                }))
-                            }
-                            (Some(_), Some(_)) => return Err(Error::TypeMismatch),
-                            _ => return Err(Error::StackUnderflow),
-                        }
-                    }
-                    '!' | '~' => {
-                        match stack.pop() {
-                            Some(Number(x)) => {
-                                stack.push(Number(match cur {
-                                    '!' if x > 0 => 0,
-                                    '!' => 1,
-                                    '~' => !x,
-                                    _ => unreachable!("logic error"),
-                                }))
-                            }
-                            Some(_) => return Err(Error::TypeMismatch),
-                            None => return Err(Error::StackUnderflow),
-                        }
+                    '+' | '-' | '/' | '*' | '^' | '&' | '|' | 'm' | '=' | '>' | '<' | 'A' | 'O' => {
+                        binary(c, &mut stack)?
                     }
+                    '!' | '~' => unary(c, &mut stack)?,
                     'i' => {
                         match (&mparams[0], &mparams[1]) {
                             (&Number(x), &Number(y)) => {
-                                mparams[0] = Number(x + 1);
-                                mparams[1] = Number(y + 1);
+                                mparams[0] = Number(x.wrapping_add(1));
+                                mparams[1] = Number(y.wrapping_add(1));
                             }
-                            (_, _) => return Err(Error::TypeMismatch),
+                            (_, _) => return Err(Error::TypeMismatch.into()),
                         }
                     }
 
@@ -266,13 +280,12 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                     'd' | 'o' | 'x' | 'X' | 's' => {
                         if let Some(arg) = stack.pop() {
                             let flags = Flags::new();
-                            let res = try!(format(arg, FormatOp::from_char(cur), flags));
-                            output.extend(res);
+                            format(arg, FormatOp::from_char(cur), flags, output)?;
                         } else {
-                            return Err(Error::StackUnderflow);
+                            return Err(Error::StackUnderflow.into());
                         }
                     }
-                    ':' | '#' | ' ' | '.' | '0'...'9' => {
+                    ':' | '#' | ' ' | '.' | '0'..='9' => {
                         let mut flags = Flags::new();
                         let mut fstate = FormatStateFlags;
                         match cur {
@@ -280,7 +293,7 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                             '#' => flags.alternate = true,
                             ' ' => flags.space = true,
                             '.' => fstate = FormatStatePrecision,
-                            '0'...'9' => {
+                            '0'..='9' => {
                                 flags.width = cur as usize - '0' as usize;
                                 fstate = FormatStateWidth;
                             }
@@ -295,51 +308,51 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                         match stack.pop() {
                             Some(Number(0)) => state = SeekIfElse(0),
                             Some(Number(_)) => (),
-                            Some(_) => return Err(Error::TypeMismatch),
-                            None => return Err(Error::StackUnderflow),
+                            Some(_) => return Err(Error::TypeMismatch.into()),
+                            None => return Err(Error::StackUnderflow.into()),
                         }
                     }
                     'e' => state = SeekIfEnd(0),
                     ';' => (),
-                    c => return Err(Error::UnrecognizedFormatOption(c)),
+                    c => return Err(Error::UnrecognizedFormatOption(c).into()),
                 }
             }
             PushParam => {
                 // params are 1-indexed
                 stack.push(mparams[match cur.to_digit(10) {
-                               Some(d) => d as usize - 1,
-                               None => return Err(Error::InvalidParameterIndex(cur)),
+                               Some(d) if d > 0 => d as usize - 1,
+                               _ => return Err(Error::InvalidParameterIndex(cur).into()),
                            }]
                            .clone());
             }
             SetVar => {
-                if cur >= 'A' && cur <= 'Z' {
+                if cur.is_ascii_uppercase() {
                     if let Some(arg) = stack.pop() {
                         let idx = (cur as u8) - b'A';
                         vars.sta[idx as usize] = arg;
                     } else {
-                        return Err(Error::StackUnderflow);
+                        return Err(Error::StackUnderflow.into());
                     }
-                } else if cur >= 'a' && cur <= 'z' {
+                } else if cur.is_ascii_lowercase() {
                     if let Some(arg) = stack.pop() {
                         let idx = (cur as u8) - b'a';
                         vars.dyn[idx as usize] = arg;
                     } else {
-                        return Err(Error::StackUnderflow);
+                        return Err(Error::StackUnderflow.into());
                     }
                 } else {
-                    return Err(Error::InvalidVariableName(cur));
+                    return Err(Error::InvalidVariableName(cur).into());
                 }
             }
             GetVar => {
-                if cur >= 'A' && cur <= 'Z' {
+                if cur.is_ascii_uppercase() {
                     let idx = (cur as u8) - b'A';
                     stack.push(vars.sta[idx as usize].clone());
-                } else if cur >= 'a' && cur <= 'z' {
+                } else if cur.is_ascii_lowercase() {
                     let idx = (cur as u8) - b'a';
                     stack.push(vars.dyn[idx as usize].clone());
                 } else {
-                    return Err(Error::InvalidVariableName(cur));
+                    return Err(Error::InvalidVariableName(cur).into());
                 }
             }
             CharConstant => {
@@ -348,7 +361,7 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
             }
             CharClose => {
                 if cur != '\'' {
-                    return Err(Error::MalformedCharacterConstant);
+                    return Err(Error::MalformedCharacterConstant.into());
                 }
             }
             IntConstant(i) => {
@@ -361,10 +374,10 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                             state = IntConstant(i);
                             old_state = Nothing;
                         }
-                        None => return Err(Error::IntegerConstantOverflow),
+                        None => return Err(Error::IntegerConstantOverflow.into()),
                     }
                 } else {
-                    return Err(Error::MalformedIntegerConstant);
+                    return Err(Error::MalformedIntegerConstant.into());
                 }
             }
             FormatPattern(ref mut flags, ref mut fstate) => {
@@ -372,12 +385,11 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                 match (*fstate, cur) {
                     (_, 'd') | (_, 'o') | (_, 'x') | (_, 'X') | (_, 's') => {
                         if let Some(arg) = stack.pop() {
-                            let res = try!(format(arg, FormatOp::from_char(cur), *flags));
-                            output.extend(res);
+                            format(arg, FormatOp::from_char(cur), *flags, output)?;
                             // will cause state to go to Nothing
                             old_state = FormatPattern(*flags, *fstate);
                         } else {
-                            return Err(Error::StackUnderflow);
+                            return Err(Error::StackUnderflow.into());
                         }
                     }
                     (FormatStateFlags, '#') => {
@@ -392,33 +404,33 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
                     (FormatStateFlags, ' ') => {
                         flags.space = true;
                     }
-                    (FormatStateFlags, '0'...'9') => {
+                    (FormatStateFlags, '0'..='9') => {
                         flags.width = cur as usize - '0' as usize;
                         *fstate = FormatStateWidth;
                     }
                     (FormatStateFlags, '.') => {
                         *fstate = FormatStatePrecision;
                     }
-                    (FormatStateWidth, '0'...'9') => {
+                    (FormatStateWidth, '0'..='9') => {
                         flags.width = match flags.width.checked_mul(10).and_then(|w| {
                             w.checked_add(cur as usize - '0' as usize)
                         }) {
                             Some(width) => width,
-                            None => return Err(Error::FormatWidthOverflow),
+                            None => return Err(Error::FormatWidthOverflow.into()),
                         }
                     }
                     (FormatStateWidth, '.') => {
                         *fstate = FormatStatePrecision;
                     }
-                    (FormatStatePrecision, '0'...'9') => {
+                    (FormatStatePrecision, '0'..='9') => {
                         flags.precision = match flags.precision.checked_mul(10).and_then(|w| {
                             w.checked_add(cur as usize - '0' as usize)
                         }) {
                             Some(precision) => precision,
-                            None => return Err(Error::FormatPrecisionOverflow),
+                            None => return Err(Error::FormatPrecisionOverflow.into()),
                         }
                     }
-                    _ => return Err(Error::UnrecognizedFormatOption(cur)),
+                    _ => return Err(Error::UnrecognizedFormatOption(cur).into()),
                 }
             }
             SeekIfElse(level) => {
@@ -466,10 +478,906 @@ pub fn expand(cap: &[u8], params: &[Param], vars: &mut Variables) -> Result<Vec<
             state = Nothing;
         }
     }
-    Ok(output)
+    if let Some(start) = literal {
+        output.write_all(&cap[start..])?;
+    }
+    Ok(())
 }
 
-#[derive(Copy, PartialEq, Clone)]
+/// An operation of a compiled `Program`.
+#[derive(Clone, Debug)]
+enum Op {
+    /// Output a range of the capability.
+    Literal(usize, usize),
+    /// `%c`
+    Char,
+    /// `%p1` to `%p9`, as an index.
+    PushParam(usize),
+    /// `%PA` to `%PZ`, as an index.
+    SetStatic(usize),
+    /// `%Pa` to `%Pz`, as an index.
+    SetDynamic(usize),
+    GetStatic(usize),
+    GetDynamic(usize),
+    /// `%'c'` and `%{n}`
+    PushNumber(i32),
+    /// `%l`
+    Length,
+    Binary(u8),
+    Unary(u8),
+    /// `%i`
+    Increment,
+    Format(FormatOp, Flags),
+    /// `%t`: pop a number, and continue at the given op if it is 0.
+    Test(usize),
+    /// `%e`, or the end of a part compiled on its own.
+    Jump(usize),
+    /// A syntax error, reported when it is reached.
+    Fail(Error),
+    End,
+}
+
+/// A compiled parameterized capability, to be expanded many times.
+///
+/// Expanding a program gives the same output as `expand` on the capability, without parsing it
+/// again.
+#[derive(Clone, Debug)]
+pub struct Program {
+    cap: Vec<u8>,
+    ops: Vec<Op>,
+}
+
+/// Where `expand` goes on after skipping a conditional branch starting at `pos`: after the
+/// matching `%;`, or also the matching `%e` if `to_else`.
+fn skip_branch(cap: &[u8], mut pos: usize, to_else: bool) -> usize {
+    let mut level = 0;
+    let mut percent = false;
+    while pos < cap.len() {
+        let c = cap[pos];
+        pos += 1;
+        if !percent {
+            percent = c == b'%';
+            continue;
+        }
+        percent = false;
+        match c {
+            b';' if level == 0 => return pos,
+            b';' => level -= 1,
+            b'e' if to_else && level == 0 => return pos,
+            b'?' => level += 1,
+            _ => (),
+        }
+    }
+    pos
+}
+
+struct Compiler<'a> {
+    cap: &'a [u8],
+    ops: Vec<Op>,
+    /// The op each compiled position of the capability starts at.
+    starts: HashMap<usize, usize>,
+    /// The `Test` and `Jump` ops, and the positions they go to.
+    branches: Vec<(usize, usize)>,
+}
+
+impl<'a> Compiler<'a> {
+    /// Compile the capability from `pos` until its end or a position compiled before.
+    ///
+    /// Returns the syntax error ending the part if no branch comes before it, so that every
+    /// expansion reaching this part reaches the error.
+    fn part(&mut self, mut pos: usize) -> Option<Error> {
+        let branches = self.branches.len();
+        loop {
+            if let Some(&op) = self.starts.get(&pos) {
+                self.ops.push(Op::Jump(op));
+                return None;
+            }
+            self.starts.insert(pos, self.ops.len());
+            if pos >= self.cap.len() {
+                self.ops.push(Op::End);
+                return None;
+            }
+            if self.cap[pos] != b'%' {
+                let end = (pos + 1..self.cap.len())
+                    .find(|&i| self.cap[i] == b'%' || self.starts.contains_key(&i))
+                    .unwrap_or(self.cap.len());
+                self.ops.push(Op::Literal(pos, end));
+                pos = end;
+                continue;
+            }
+            match self.operation(pos + 1) {
+                Ok(next) => pos = next,
+                Err(e) => {
+                    self.ops.push(Op::Fail(e.clone()));
+                    return if self.branches.len() == branches { Some(e) } else { None };
+                }
+            }
+        }
+    }
+
+    /// Compile the operation after a `%` at `pos`, returning the position after it.
+    ///
+    /// Like `expand`, an operation cut short by the end of the capability is ignored.
+    fn operation(&mut self, mut pos: usize) -> Result<usize, Error> {
+        let cap = self.cap;
+        let next = |pos: &mut usize| -> Option<u8> {
+            let c = cap.get(*pos).cloned();
+            *pos += 1;
+            c
+        };
+        let c = match next(&mut pos) {
+            Some(c) => c,
+            None => return Ok(cap.len()),
+        };
+        let op = match c {
+            b'%' => Op::Literal(pos - 1, pos),
+            b'c' => Op::Char,
+            b'p' => {
+                match next(&mut pos) {
+                    Some(d @ b'1'..=b'9') => Op::PushParam((d - b'1') as usize),
+                    Some(d) => return Err(Error::InvalidParameterIndex(d as char)),
+                    None => return Ok(cap.len()),
+                }
+            }
+            b'P' | b'g' => {
+                let set = c == b'P';
+                match next(&mut pos) {
+                    Some(v @ b'A'..=b'Z') if set => Op::SetStatic((v - b'A') as usize),
+                    Some(v @ b'a'..=b'z') if set => Op::SetDynamic((v - b'a') as usize),
+                    Some(v @ b'A'..=b'Z') => Op::GetStatic((v - b'A') as usize),
+                    Some(v @ b'a'..=b'z') => Op::GetDynamic((v - b'a') as usize),
+                    Some(v) => return Err(Error::InvalidVariableName(v as char)),
+                    None => return Ok(cap.len()),
+                }
+            }
+            b'\'' => {
+                match next(&mut pos) {
+                    Some(c) => self.ops.push(Op::PushNumber(c as i32)),
+                    None => return Ok(cap.len()),
+                }
+                match next(&mut pos) {
+                    Some(b'\'') => return Ok(pos),
+                    Some(_) => return Err(Error::MalformedCharacterConstant),
+                    None => return Ok(cap.len()),
+                }
+            }
+            b'{' => {
+                let mut i: i32 = 0;
+                loop {
+                    match next(&mut pos) {
+                        Some(b'}') => break Op::PushNumber(i),
+                        Some(d @ b'0'..=b'9') => {
+                            i = i.checked_mul(10)
+                                 .and_then(|i| i.checked_add((d - b'0') as i32))
+                                 .ok_or(Error::IntegerConstantOverflow)?
+                        }
+                        Some(_) => return Err(Error::MalformedIntegerConstant),
+                        None => return Ok(cap.len()),
+                    }
+                }
+            }
+            b'l' => Op::Length,
+            b'+' | b'-' | b'/' | b'*' | b'^' | b'&' | b'|' | b'm' | b'=' | b'>' | b'<' | b'A' |
+            b'O' => Op::Binary(c),
+            b'!' | b'~' => Op::Unary(c),
+            b'i' => Op::Increment,
+            b'd' | b'o' | b'x' | b'X' | b's' => {
+                Op::Format(FormatOp::from_char(c as char), Flags::new())
+            }
+            b':' | b'#' | b' ' | b'.' | b'0'..=b'9' => {
+                let mut flags = Flags::new();
+                let mut fstate = FormatStateFlags;
+                match c {
+                    b'#' => flags.alternate = true,
+                    b' ' => flags.space = true,
+                    b'.' => fstate = FormatStatePrecision,
+                    b'0'..=b'9' => {
+                        flags.width = (c - b'0') as usize;
+                        fstate = FormatStateWidth;
+                    }
+                    _ => (),
+                }
+                loop {
+                    let c = match next(&mut pos) {
+                        Some(c) => c,
+                        None => return Ok(cap.len()),
+                    };
+                    match (fstate, c) {
+                        (_, b'd') | (_, b'o') | (_, b'x') | (_, b'X') | (_, b's') => {
+                            break Op::Format(FormatOp::from_char(c as char), flags)
+                        }
+                        (FormatStateFlags, b'#') => flags.alternate = true,
+                        (FormatStateFlags, b'-') => flags.left = true,
+                        (FormatStateFlags, b'+') => flags.sign = true,
+                        (FormatStateFlags, b' ') => flags.space = true,
+                        (FormatStateFlags, b'0'..=b'9') => {
+                            flags.width = (c - b'0') as usize;
+                            fstate = FormatStateWidth;
+                        }
+                        (FormatStateFlags, b'.') | (FormatStateWidth, b'.') => {
+                            fstate = FormatStatePrecision
+                        }
+                        (FormatStateWidth, b'0'..=b'9') => {
+                            flags.width = flags.width
+                                               .checked_mul(10)
+                                               .and_then(|w| w.checked_add((c - b'0') as usize))
+                                               .ok_or(Error::FormatWidthOverflow)?
+                        }
+                        (FormatStatePrecision, b'0'..=b'9') => {
+                            flags.precision = flags.precision
+                                                   .checked_mul(10)
+                                                   .and_then(|p| p.checked_add((c - b'0') as usize))
+                                                   .ok_or(Error::FormatPrecisionOverflow)?
+                        }
+                        _ => return Err(Error::UnrecognizedFormatOption(c as char)),
+                    }
+                }
+            }
+            b'?' | b';' => return Ok(pos),
+            b't' | b'e' => {
+                self.branches.push((self.ops.len(), skip_branch(cap, pos, c == b't')));
+                if c == b't' { Op::Test(0) } else { Op::Jump(0) }
+            }
+            c => return Err(Error::UnrecognizedFormatOption(c as char)),
+        };
+        self.ops.push(op);
+        Ok(pos)
+    }
+}
+
+impl Program {
+    /// Compile a parameterized capability.
+    ///
+    /// Syntax errors that every expansion would run into are reported here; others are
+    /// reported by `expand` when it gets to them.
+    pub fn compile(cap: &[u8]) -> Result<Program, Error> {
+        match Program::build(cap) {
+            (_, Some(e)) => Err(e),
+            (program, None) => Ok(program),
+        }
+    }
+
+    /// Compile a capability, along with the syntax error every expansion runs into, if any.
+    fn build(cap: &[u8]) -> (Program, Option<Error>) {
+        let mut compiler = Compiler {
+            cap,
+            ops: Vec::new(),
+            starts: HashMap::new(),
+            branches: Vec::new(),
+        };
+        let error = compiler.part(0);
+        // Skipping a branch may continue in the middle of what was compiled as a literal, or
+        // at a position `expand` never gets to otherwise.
+        let mut i = 0;
+        while let Some(&(_, pos)) = compiler.branches.get(i) {
+            if !compiler.starts.contains_key(&pos) {
+                compiler.part(pos);
+            }
+            i += 1;
+        }
+        for &(op, pos) in &compiler.branches {
+            let target = compiler.starts[&pos];
+            match compiler.ops[op] {
+                Op::Test(ref mut t) | Op::Jump(ref mut t) => *t = target,
+                _ => unreachable!("logic error"),
+            }
+        }
+        (Program {
+            cap: cap.to_vec(),
+            ops: compiler.ops,
+        },
+         error)
+    }
+
+    /// Expand the program, like `expand`.
+    pub fn expand(&self, params: &[Param], vars: &mut Variables) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::with_capacity(self.cap.len());
+        self.expand_into(params, vars, &mut output).map(|()| output).map_err(Failure::into_error)
+    }
+
+    /// Expand the program into `out`, like `expand_to`.
+    pub fn expand_to<W: io::Write + ?Sized>(&self,
+                                            params: &[Param],
+                                            vars: &mut Variables,
+                                            out: &mut W)
+                                            -> io::Result<()> {
+        self.expand_into(params, vars, out).map_err(io::Error::from)
+    }
+
+    fn expand_into<W: io::Write + ?Sized>(&self,
+                                          params: &[Param],
+                                          vars: &mut Variables,
+                                          output: &mut W)
+                                          -> Result<(), Failure> {
+        let mut stack: Vec<Param> = Vec::new();
+        let mut mparams = [Number(0), Number(0), Number(0), Number(0), Number(0), Number(0),
+                           Number(0), Number(0), Number(0)];
+        for (dst, src) in mparams.iter_mut().zip(params.iter()) {
+            *dst = (*src).clone();
+        }
+
+        let mut pc = 0;
+        loop {
+            let op = &self.ops[pc];
+            pc += 1;
+            match *op {
+                Op::Literal(start, end) => output.write_all(&self.cap[start..end])?,
+                Op::Char => {
+                    match stack.pop() {
+                        Some(Number(0)) => output.write_all(&[128u8])?,
+                        Some(Number(c)) => output.write_all(&[c as u8])?,
+                        Some(_) => return Err(Error::TypeMismatch.into()),
+                        None => return Err(Error::StackUnderflow.into()),
+                    }
+                }
+                Op::PushParam(i) => stack.push(mparams[i].clone()),
+                Op::SetStatic(i) => vars.sta[i] = stack.pop().ok_or(Error::StackUnderflow)?,
+                Op::SetDynamic(i) => vars.dyn[i] = stack.pop().ok_or(Error::StackUnderflow)?,
+                Op::GetStatic(i) => stack.push(vars.sta[i].clone()),
+                Op::GetDynamic(i) => stack.push(vars.dyn[i].clone()),
+                Op::PushNumber(n) => stack.push(Number(n)),
+                Op::Length => {
+                    match stack.pop() {
+                        Some(Words(s)) => stack.push(Number(s.len() as i32)),
+                        Some(_) => return Err(Error::TypeMismatch.into()),
+                        None => return Err(Error::StackUnderflow.into()),
+                    }
+                }
+                Op::Binary(c) => binary(c, &mut stack)?,
+                Op::Unary(c) => unary(c, &mut stack)?,
+                Op::Increment => {
+                    match (&mparams[0], &mparams[1]) {
+                        (&Number(x), &Number(y)) => {
+                            mparams[0] = Number(x.wrapping_add(1));
+                            mparams[1] = Number(y.wrapping_add(1));
+                        }
+                        (_, _) => return Err(Error::TypeMismatch.into()),
+                    }
+                }
+                Op::Format(op, flags) => {
+                    let arg = stack.pop().ok_or(Error::StackUnderflow)?;
+                    format(arg, op, flags, output)?;
+                }
+                Op::Test(target) => {
+                    match stack.pop() {
+                        Some(Number(0)) => pc = target,
+                        Some(Number(_)) => (),
+                        Some(_) => return Err(Error::TypeMismatch.into()),
+                        None => return Err(Error::StackUnderflow.into()),
+                    }
+                }
+                Op::Jump(target) => pc = target,
+                Op::Fail(ref e) => return Err(e.clone().into()),
+                Op::End => return Ok(()),
+            }
+        }
+    }
+}
+
+/// How a capability uses one of its parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    /// The parameter isn't read.
+    Unused,
+    /// The parameter is read, but nothing tells its type, e.g. it is only stored in a variable.
+    Any,
+    /// The parameter is used as a number, by `%d`, `%c`, `%t` or an operator.
+    Number,
+    /// The parameter is used as a string, by `%s` or `%l`.
+    Words,
+    /// The parameter is used both as a number and as a string.
+    Mixed,
+}
+
+impl ParamType {
+    fn merge(&mut self, other: ParamType) {
+        use self::ParamType::*;
+        *self = match (*self, other) {
+            (Unused, t) | (t, Unused) | (Any, t) | (t, Any) => t,
+            (a, b) if a == b => a,
+            _ => Mixed,
+        };
+    }
+}
+
+/// What static analysis tells about a parameterized capability.
+///
+/// Every branch of the capability is followed, whatever the parameters and variables, so
+/// the analysis may report errors no actual expansion runs into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The number of parameters the capability reads, i.e. the highest `%p` used.
+    pub arity: usize,
+    /// How each parameter is used, `params[0]` being `%p1`.
+    pub params: [ParamType; 9],
+    /// The variables read with `%g`, in order. Static variables are uppercase and dynamic ones
+    /// lowercase.
+    pub reads: Vec<char>,
+    /// The variables written with `%P`, in order.
+    pub writes: Vec<char>,
+    /// The errors an expansion can run into, in the order they were found. Type mismatches
+    /// that depend on the parameters or on variables set beforehand aren't among them.
+    pub errors: Vec<Error>,
+}
+
+impl Analysis {
+    /// Whether the stack underflows on some branch.
+    pub fn can_underflow(&self) -> bool {
+        self.errors.contains(&Error::StackUnderflow)
+    }
+
+    /// Whether `params` are enough for the capability and of the types it uses.
+    pub fn accepts(&self, params: &[Param]) -> bool {
+        params.len() >= self.arity &&
+        self.params.iter().zip(params).all(|(t, p)| {
+            !matches!((*t, p),
+                      (ParamType::Number, &Words(_)) | (ParamType::Words, &Number(_)) |
+                      (ParamType::Mixed, _))
+        })
+    }
+
+    fn add_var(vars: &mut Vec<char>, index: usize) {
+        let name = if index < 26 { b'A' + index as u8 } else { b'a' + (index - 26) as u8 } as char;
+        if let Err(i) = vars.binary_search(&name) {
+            vars.insert(i, name);
+        }
+    }
+
+    /// Use a value of the stack as the given type.
+    fn pop(&mut self, stack: &mut Vec<Value>, ty: ParamType) -> Result<Value, Error> {
+        let value = stack.pop().ok_or(Error::StackUnderflow)?;
+        match value {
+            Value::Number if ty == ParamType::Words => return Err(Error::TypeMismatch),
+            Value::Param(i) => self.params[i].merge(ty),
+            _ => (),
+        }
+        Ok(value)
+    }
+
+    /// Follow the path from `state` until it ends, queueing the branches it skips.
+    fn follow(&mut self,
+              ops: &[Op],
+              mut state: State,
+              pending: &mut Vec<State>,
+              seen: &mut HashSet<State>)
+              -> Result<(), Error> {
+        loop {
+            let op = &ops[state.pc];
+            state.pc += 1;
+            match *op {
+                Op::Literal(..) => (),
+                Op::Char => {
+                    self.pop(&mut state.stack, ParamType::Number)?;
+                }
+                Op::PushParam(i) => {
+                    self.arity = self.arity.max(i + 1);
+                    self.params[i].merge(ParamType::Any);
+                    state.stack.push(Value::Param(i));
+                }
+                Op::SetStatic(i) | Op::SetDynamic(i) => {
+                    let index = if let Op::SetStatic(_) = *op { i } else { 26 + i };
+                    state.vars[index] = self.pop(&mut state.stack, ParamType::Any)?;
+                    Analysis::add_var(&mut self.writes, index);
+                }
+                Op::GetStatic(i) | Op::GetDynamic(i) => {
+                    let index = if let Op::GetStatic(_) = *op { i } else { 26 + i };
+                    state.stack.push(state.vars[index]);
+                    Analysis::add_var(&mut self.reads, index);
+                }
+                Op::PushNumber(_) => state.stack.push(Value::Number),
+                Op::Length => {
+                    self.pop(&mut state.stack, ParamType::Words)?;
+                    state.stack.push(Value::Number);
+                }
+                Op::Binary(_) => {
+                    if state.stack.len() < 2 {
+                        return Err(Error::StackUnderflow);
+                    }
+                    self.pop(&mut state.stack, ParamType::Number)?;
+                    self.pop(&mut state.stack, ParamType::Number)?;
+                    state.stack.push(Value::Number);
+                }
+                Op::Unary(_) => {
+                    self.pop(&mut state.stack, ParamType::Number)?;
+                    state.stack.push(Value::Number);
+                }
+                Op::Increment => {
+                    self.params[0].merge(ParamType::Number);
+                    self.params[1].merge(ParamType::Number);
+                }
+                Op::Format(FormatString, _) => {
+                    self.pop(&mut state.stack, ParamType::Words)?;
+                }
+                Op::Format(..) => {
+                    self.pop(&mut state.stack, ParamType::Number)?;
+                }
+                Op::Test(target) => {
+                    self.pop(&mut state.stack, ParamType::Number)?;
+                    let mut skipped = State { pc: target, ..state.clone() };
+                    if seen.len() >= MAX_STATES {
+                        // Stop telling values apart, so that the number of states stays
+                        // bounded by the number of ops and stack depths.
+                        skipped.stack.iter_mut().for_each(|v| *v = Value::Unknown);
+                        skipped.vars = [Value::Unknown; 52];
+                    }
+                    if seen.insert(skipped.clone()) {
+                        pending.push(skipped);
+                    }
+                }
+                Op::Jump(target) => state.pc = target,
+                Op::Fail(ref e) => return Err(e.clone()),
+                Op::End => return Ok(()),
+            }
+        }
+    }
+}
+
+/// The number of branch states the analysis tells apart, since following every combination of
+/// branches with what they store can take exponential time.
+const MAX_STATES: usize = 4096;
+
+/// What the analysis knows about a value on the stack or in a variable.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Value {
+    Number,
+    Param(usize),
+    /// A variable that wasn't set by the capability.
+    Unknown,
+}
+
+/// Where the analysis is on a path: the next op, the stack, and the static and dynamic
+/// variables.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    pc: usize,
+    stack: Vec<Value>,
+    vars: [Value; 52],
+}
+
+impl Program {
+    /// Analyze the program, following every branch.
+    pub fn analyze(&self) -> Analysis {
+        let mut analysis = Analysis {
+            arity: 0,
+            params: [ParamType::Unused; 9],
+            reads: Vec::new(),
+            writes: Vec::new(),
+            errors: Vec::new(),
+        };
+        let mut pending = vec![State {
+                                   pc: 0,
+                                   stack: Vec::new(),
+                                   vars: [Value::Unknown; 52],
+                               }];
+        let mut seen = HashSet::new();
+        while let Some(state) = pending.pop() {
+            if let Err(e) = analysis.follow(&self.ops, state, &mut pending, &mut seen) {
+                if !analysis.errors.contains(&e) {
+                    analysis.errors.push(e);
+                }
+            }
+        }
+        analysis
+    }
+}
+
+/// Analyze a parameterized capability: the parameters and variables it uses, and the errors
+/// its expansion can run into.
+///
+/// ```
+/// use terminfo::parm::{analyze, ParamType};
+///
+/// let cup = analyze(b"\x1b[%i%p1%d;%p2%dH");
+/// assert_eq!(cup.arity, 2);
+/// assert_eq!(cup.params[..2], [ParamType::Number, ParamType::Number]);
+/// assert!(cup.errors.is_empty());
+///
+/// assert!(analyze(b"%?%p1%t%d%;").can_underflow());
+/// ```
+pub fn analyze(cap: &[u8]) -> Analysis {
+    Program::build(cap).0.analyze()
+}
+
+/// A termcap percent code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TermcapCode {
+    Text(u8),
+    /// `%%`
+    Percent,
+    /// `%d`, `%2` or `%3`, as the number of digits.
+    Decimal(usize),
+    /// `%.`, or `%+x` with the amount added.
+    Char(u8),
+    /// `%s`
+    String,
+    /// `%>xy`
+    Greater(u8, u8),
+    /// `%r`
+    Reverse,
+    /// `%i`
+    Increment,
+    /// `%n`
+    Xor,
+    /// `%B`
+    Bcd,
+    /// `%D`
+    Delta,
+}
+
+impl TermcapCode {
+    /// Whether the code consumes a parameter.
+    fn consumes(self) -> bool {
+        matches!(self, TermcapCode::Decimal(_) | TermcapCode::Char(_) | TermcapCode::String)
+    }
+
+    /// Whether the code changes parameters in a way terminfo can only do with variables.
+    fn modifies(self) -> bool {
+        matches!(self,
+                 TermcapCode::Greater(..) | TermcapCode::Xor | TermcapCode::Bcd |
+                 TermcapCode::Delta)
+    }
+}
+
+/// Split a termcap capability into codes. Like `expand`, a code cut short by the end of the
+/// capability is ignored, except that a lone `%` is kept as text like `tic` does.
+fn termcap_codes(cap: &[u8]) -> Result<Vec<TermcapCode>, Error> {
+    let mut codes = Vec::new();
+    let mut bytes = cap.iter().cloned();
+    while let Some(c) = bytes.next() {
+        if c != b'%' {
+            codes.push(TermcapCode::Text(c));
+            continue;
+        }
+        let code = match bytes.next() {
+            Some(b'%') => TermcapCode::Percent,
+            Some(b'd') => TermcapCode::Decimal(0),
+            Some(b'2') => TermcapCode::Decimal(2),
+            Some(b'3') => TermcapCode::Decimal(3),
+            Some(b'.') => TermcapCode::Char(0),
+            Some(b's') => TermcapCode::String,
+            Some(b'+') => {
+                match bytes.next() {
+                    Some(x) => TermcapCode::Char(x),
+                    None => break,
+                }
+            }
+            Some(b'>') => {
+                match (bytes.next(), bytes.next()) {
+                    (Some(x), Some(y)) => TermcapCode::Greater(x, y),
+                    _ => break,
+                }
+            }
+            Some(b'r') => TermcapCode::Reverse,
+            Some(b'i') => TermcapCode::Increment,
+            Some(b'n') => TermcapCode::Xor,
+            Some(b'B') => TermcapCode::Bcd,
+            Some(b'D') => TermcapCode::Delta,
+            Some(c) => return Err(Error::UnrecognizedFormatOption(c as char)),
+            None => TermcapCode::Text(b'%'),
+        };
+        codes.push(code);
+    }
+    Ok(codes)
+}
+
+/// Translate a capability with termcap percent codes into one with terminfo's.
+///
+/// Termcap codes go through the parameters in order:
+///
+/// * `%d` outputs the next parameter like `%d`, and `%2` and `%3` its last two or three
+///   digits like `%.2d` and `%.3d`.
+/// * `%.` outputs it like `%c`, and `%+x` does after adding the character `x` to it.
+/// * `%s` outputs it like `%s`, an extension of ncurses.
+/// * Like with ncurses, `%+x`, `%2` and `%3` leave the parameter as they output it.
+/// * `%>xy` adds the character `y` to it if it is greater than `x`, without moving on.
+/// * `%B` and `%D` turn it into binary coded decimal and Delta Data's reverse coding.
+/// * `%r` swaps the first two parameters, `%i` adds one to them, and `%n` XORs them with
+///   0140.
+///
+/// The parameters are read with `%p1` to `%p9`, or, if codes like `%>` change them, first
+/// copied to the dynamic variables `a` to `i`. Using more than nine is an error.
+pub fn termcap_to_terminfo(cap: &[u8]) -> Result<Vec<u8>, Error> {
+    let codes = termcap_codes(cap)?;
+    // The number of parameters used.
+    let mut count = 0;
+    let mut next = 0;
+    for &code in &codes {
+        count = match code {
+            TermcapCode::Text(_) | TermcapCode::Percent => count,
+            TermcapCode::Reverse | TermcapCode::Increment | TermcapCode::Xor => count.max(2),
+            _ => count.max(next + 1),
+        };
+        if code.consumes() {
+            next += 1;
+        }
+    }
+    if count > 9 {
+        return Err(Error::TooManyParameters);
+    }
+    // `%+x`, `%2` and `%3` also change the parameter they output, which shows if `%r` brings
+    // it back.
+    let changes = |c: &TermcapCode| {
+        matches!(*c, TermcapCode::Char(x) if x != 0) ||
+        matches!(*c, TermcapCode::Decimal(digits) if digits != 0)
+    };
+    let vars = codes.iter().any(|c| c.modifies()) ||
+               codes.iter().skip_while(|c| !changes(c)).any(|&c| c == TermcapCode::Reverse);
+
+    let mut out = Vec::with_capacity(cap.len() * 2);
+    // Push parameter `i`.
+    let get = |out: &mut Vec<u8>, i: usize| if vars {
+        out.extend_from_slice(&[b'%', b'g', b'a' + i as u8]);
+    } else {
+        out.extend_from_slice(&[b'%', b'p', b'1' + i as u8]);
+    };
+    let set = |out: &mut Vec<u8>, i: usize| out.extend_from_slice(&[b'%', b'P', b'a' + i as u8]);
+    // Characters that aren't printable are pushed as numbers, like `tic` does.
+    let constant = |out: &mut Vec<u8>, c: u8| if (b' '..=b'~').contains(&c) && c != b'\'' {
+        out.extend_from_slice(&[b'%', b'\'', c, b'\'']);
+    } else {
+        out.extend_from_slice(format!("%{{{}}}", c).as_bytes());
+    };
+    if vars {
+        for i in 0..count {
+            out.extend_from_slice(&[b'%', b'p', b'1' + i as u8]);
+            set(&mut out, i);
+        }
+    }
+
+    let mut order = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+    next = 0;
+    for &code in &codes {
+        // Only codes after the ninth parameter that don't use one get past it.
+        let i = order[next.min(8)];
+        match code {
+            TermcapCode::Text(c) => out.push(c),
+            TermcapCode::Percent => out.extend_from_slice(b"%%"),
+            TermcapCode::Decimal(digits) => {
+                get(&mut out, i);
+                if digits != 0 {
+                    out.extend_from_slice(if digits == 2 { b"%{100}%m" } else { b"%{1000}%m" });
+                    if vars {
+                        set(&mut out, i);
+                        get(&mut out, i);
+                    }
+                }
+                let format: &[u8] = match digits {
+                    2 => b"%.2d",
+                    3 => b"%.3d",
+                    _ => b"%d",
+                };
+                out.extend_from_slice(format);
+            }
+            TermcapCode::Char(x) => {
+                get(&mut out, i);
+                if x != 0 {
+                    constant(&mut out, x);
+                    out.extend_from_slice(b"%+");
+                    if vars {
+                        set(&mut out, i);
+                        get(&mut out, i);
+                    }
+                }
+                out.extend_from_slice(b"%c");
+            }
+            TermcapCode::String => {
+                get(&mut out, i);
+                out.extend_from_slice(b"%s");
+            }
+            TermcapCode::Greater(x, y) => {
+                out.extend_from_slice(b"%?");
+                get(&mut out, i);
+                constant(&mut out, x);
+                out.extend_from_slice(b"%>%t");
+                get(&mut out, i);
+                constant(&mut out, y);
+                out.extend_from_slice(b"%+");
+                set(&mut out, i);
+                out.extend_from_slice(b"%;");
+            }
+            TermcapCode::Reverse => order.swap(0, 1),
+            TermcapCode::Increment if !vars => out.extend_from_slice(b"%i"),
+            TermcapCode::Increment | TermcapCode::Xor => {
+                let operand: &[u8] = match code {
+                    TermcapCode::Xor => b"%{96}%^",
+                    _ => b"%{1}%+",
+                };
+                for i in 0..2 {
+                    get(&mut out, i);
+                    out.extend_from_slice(operand);
+                    set(&mut out, i);
+                }
+            }
+            TermcapCode::Bcd => {
+                get(&mut out, i);
+                out.extend_from_slice(b"%{10}%/%{16}%*");
+                get(&mut out, i);
+                out.extend_from_slice(b"%{10}%m%+");
+                set(&mut out, i);
+            }
+            TermcapCode::Delta => {
+                get(&mut out, i);
+                get(&mut out, i);
+                out.extend_from_slice(b"%{16}%m%{2}%*%-");
+                set(&mut out, i);
+            }
+        }
+        if code.consumes() {
+            next += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// Expand a capability with termcap percent codes, as described in `termcap_to_terminfo`.
+///
+/// If codes like `%>` change the parameters, they are copied to the dynamic variables `a` to `i`
+/// of `vars`, overwriting what those held.
+pub fn expand_termcap(cap: &[u8],
+                      params: &[Param],
+                      vars: &mut Variables)
+                      -> Result<Vec<u8>, Error> {
+    expand(&termcap_to_terminfo(cap)?, params, vars)
+}
+
+/// Expand a capability with termcap percent codes into `out`, like `expand_termcap`, which
+/// describes how `vars` is used.
+pub fn expand_termcap_to<W: io::Write + ?Sized>(cap: &[u8],
+                                                params: &[Param],
+                                                vars: &mut Variables,
+                                                out: &mut W)
+                                                -> io::Result<()> {
+    expand_to(&termcap_to_terminfo(cap)?, params, vars, out)
+}
+
+/// Apply a binary operator (`%+`, `%=`, ...) to the top two numbers on the stack.
+///
+/// Like ncurses, arithmetic wraps around and division by zero gives 0.
+fn binary(op: u8, stack: &mut Vec<Param>) -> Result<(), Error> {
+    match (stack.pop(), stack.pop()) {
+        (Some(Number(y)), Some(Number(x))) => {
+            stack.push(Number(match op {
+                b'+' => x.wrapping_add(y),
+                b'-' => x.wrapping_sub(y),
+                b'*' => x.wrapping_mul(y),
+                b'/' | b'm' if y == 0 => 0,
+                b'/' => x.wrapping_div(y),
+                b'|' => x | y,
+                b'&' => x & y,
+                b'^' => x ^ y,
+                b'm' => x.wrapping_rem(y),
+                b'=' => (x == y) as i32,
+                b'<' => (x < y) as i32,
+                b'>' => (x > y) as i32,
+                b'A' => (x > 0 && y > 0) as i32,
+                b'O' => (x > 0 || y > 0) as i32,
+                _ => unreachable!("logic error"),
+            }));
+            Ok(())
+        }
+        (Some(_), Some(_)) => Err(Error::TypeMismatch),
+        _ => Err(Error::StackUnderflow),
+    }
+}
+
+/// Apply a unary operator (`%!` or `%~`) to the number on top of the stack.
+fn unary(op: u8, stack: &mut Vec<Param>) -> Result<(), Error> {
+    match stack.pop() {
+        Some(Number(x)) => {
+            stack.push(Number(match op {
+                b'!' if x > 0 => 0,
+                b'!' => 1,
+                b'~' => !x,
+                _ => unreachable!("logic error"),
+            }));
+            Ok(())
+        }
+        Some(_) => Err(Error::TypeMismatch),
+        None => Err(Error::StackUnderflow),
+    }
+}
+
+#[derive(Copy, PartialEq, Clone, Debug)]
 struct Flags {
     width: usize,
     precision: usize,
@@ -492,7 +1400,7 @@ impl Flags {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 enum FormatOp {
     FormatDigit,
     FormatOctal,
@@ -514,78 +1422,128 @@ impl FormatOp {
     }
 }
 
-fn format(val: Param, op: FormatOp, flags: Flags) -> Result<Vec<u8>, Error> {
-    let mut s = match val {
+/// Write the digits of `n` in base `radix` to the end of `buf`, returning them.
+fn digits(mut n: u32, radix: u32, upper: bool, buf: &mut [u8; 32]) -> &[u8] {
+    let table: &[u8; 16] = if upper { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = table[(n % radix) as usize];
+        n /= radix;
+        if n == 0 {
+            return &buf[start..];
+        }
+    }
+}
+
+/// Write `n` copies of `c`.
+fn pad<W: io::Write + ?Sized>(out: &mut W, c: u8, n: usize) -> io::Result<()> {
+    const CHUNK: usize = 32;
+    let chunk = [c; CHUNK];
+    let mut n = n;
+    while n > 0 {
+        let len = n.min(CHUNK);
+        out.write_all(&chunk[..len])?;
+        n -= len;
+    }
+    Ok(())
+}
+
+/// Write a parameter formatted like C's `printf`.
+///
+/// The conversion is made up of a prefix (sign or radix), zeros up to the precision, and the
+/// digits, and is padded to the width with spaces.
+fn format<W: io::Write + ?Sized>(val: Param,
+                                 op: FormatOp,
+                                 flags: Flags,
+                                 out: &mut W)
+                                 -> Result<(), Failure> {
+    let mut buf = [0; 32];
+    let (prefix, zeros, body): (&[u8], usize, &[u8]) = match val {
         Number(d) => {
             match op {
                 FormatDigit => {
+                    let body = digits(d.unsigned_abs(), 10, false, &mut buf);
+                    // C doesn't take the sign into account in the precision.
+                    let zeros = flags.precision.saturating_sub(body.len());
                     if flags.sign {
-                        format!("{:+01$}", d, flags.precision)
+                        // Unlike C, the sign has always counted against the precision with
+                        // the `+` flag here.
+                        let sign: &[u8] = if d < 0 { b"-" } else { b"+" };
+                        (sign, flags.precision.saturating_sub(body.len() + 1), body)
                     } else if d < 0 {
-                        // C doesn't take sign into account in precision calculation.
-                        format!("{:01$}", d, flags.precision + 1)
+                        (b"-", zeros, body)
                     } else if flags.space {
-                        format!(" {:01$}", d, flags.precision)
+                        (b" ", zeros, body)
                     } else {
-                        format!("{:01$}", d, flags.precision)
+                        (b"", zeros, body)
                     }
                 }
                 FormatOctal => {
+                    let body = digits(d as u32, 8, false, &mut buf);
                     if flags.alternate {
                         // Leading octal zero counts against precision.
-                        format!("0{:01$o}", d, flags.precision.saturating_sub(1))
-                    } else {
-                        format!("{:01$o}", d, flags.precision)
-                    }
-                }
-                FormatHex => {
-                    if flags.alternate && d != 0 {
-                        format!("0x{:01$x}", d, flags.precision)
+                        (b"0", flags.precision.saturating_sub(1).saturating_sub(body.len()), body)
                     } else {
-                        format!("{:01$x}", d, flags.precision)
+                        (b"", flags.precision.saturating_sub(body.len()), body)
                     }
                 }
-                FormatHEX => {
-                    if flags.alternate && d != 0 {
-                        format!("0X{:01$X}", d, flags.precision)
-                    } else {
-                        format!("{:01$X}", d, flags.precision)
+                FormatHex | FormatHEX => {
+                    let upper = matches!(op, FormatHEX);
+                    let body = digits(d as u32, 16, upper, &mut buf);
+                    let zeros = flags.precision.saturating_sub(body.len());
+                    match (flags.alternate && d != 0, upper) {
+                        (true, false) => (b"0x", zeros, body),
+                        (true, true) => (b"0X", zeros, body),
+                        (false, _) => (b"", zeros, body),
                     }
                 }
-                FormatString => return Err(Error::TypeMismatch),
+                FormatString => return Err(Error::TypeMismatch.into()),
             }
-            .into_bytes()
         }
-        Words(s) => {
+        Words(ref s) => {
             match op {
                 FormatString => {
-                    let mut s = s.into_bytes();
+                    let s = s.as_bytes();
                     if flags.precision > 0 && flags.precision < s.len() {
-                        s.truncate(flags.precision);
+                        (b"", 0, &s[..flags.precision])
+                    } else {
+                        (b"", 0, s)
                     }
-                    s
                 }
-                _ => return Err(Error::TypeMismatch),
+                _ => return Err(Error::TypeMismatch.into()),
             }
         }
     };
-    if flags.width > s.len() {
-        let n = flags.width - s.len();
-        if flags.left {
-            s.extend(repeat(b' ').take(n));
-        } else {
-            let mut s_ = Vec::with_capacity(flags.width);
-            s_.extend(repeat(b' ').take(n));
-            s_.extend(s.into_iter());
-            s = s_;
-        }
+    write_padded(out, flags, prefix, zeros, body)?;
+    Ok(())
+}
+
+/// Write a conversion padded to the width.
+fn write_padded<W: io::Write + ?Sized>(out: &mut W,
+                                       flags: Flags,
+                                       prefix: &[u8],
+                                       zeros: usize,
+                                       body: &[u8])
+                                       -> io::Result<()> {
+    let len = prefix.len() + zeros + body.len();
+    let spaces = flags.width.saturating_sub(len);
+    if !flags.left {
+        pad(out, b' ', spaces)?;
     }
-    Ok(s)
+    out.write_all(prefix)?;
+    pad(out, b'0', zeros)?;
+    out.write_all(body)?;
+    if flags.left {
+        pad(out, b' ', spaces)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use super::{expand, Variables};
+    use super::{expand, expand_to, Program, Variables};
+    use std::io;
     use super::Param::{self, Words, Number};
     use std::result::Result::Ok;
 
@@ -665,6 +1623,34 @@ mod test {
     #[test]
     fn test_push_bad_param() {
         assert!(expand(b"%pa", &[], &mut Variables::new()).is_err());
+        assert_eq!(expand(b"%p0", &[], &mut Variables::new()),
+                   Err(super::Error::InvalidParameterIndex('0')));
+    }
+
+    #[test]
+    fn test_arithmetic_edge_cases() {
+        let cases: &[(&[u8], i32)] = &[(b"%{1}%{0}%/", 0),
+                                       (b"%{1}%{0}%m", 0),
+                                       (b"%p1%{1}%+", i32::MIN),
+                                       (b"%p2%{1}%-", i32::MAX),
+                                       (b"%p1%{2}%*", -2),
+                                       (b"%p2%{0}%{1}%-%/", i32::MIN),
+                                       (b"%p2%{0}%{1}%-%m", 0),
+                                       (b"%i%p1", i32::MIN)];
+        let params = [Number(i32::MAX), Number(i32::MIN)];
+        for &(cap, result) in cases {
+            let cap = [cap, b"%Pa"].concat();
+            let mut vars = Variables::new();
+            assert!(expand(&cap, &params, &mut vars).is_ok());
+            let mut program_vars = Variables::new();
+            assert!(Program::compile(&cap).unwrap().expand(&params, &mut program_vars).is_ok());
+            for vars in &[vars, program_vars] {
+                match vars.dyn[0] {
+                    Number(n) => assert_eq!(n, result, "{}", String::from_utf8_lossy(&cap)),
+                    _ => panic!("bad variable type"),
+                }
+            }
+        }
     }
 
     #[test]
@@ -673,15 +1659,15 @@ mod test {
         for &(op, bs) in &v {
             let s = format!("%{{1}}%{{2}}%{}%d", op);
             let res = expand(s.as_bytes(), &[], &mut Variables::new());
-            assert!(res.is_ok(), res.err().unwrap());
+            assert!(res.is_ok(), "{}", res.err().unwrap());
             assert_eq!(res.unwrap(), vec![b'0' + bs[0]]);
             let s = format!("%{{1}}%{{1}}%{}%d", op);
             let res = expand(s.as_bytes(), &[], &mut Variables::new());
-            assert!(res.is_ok(), res.err().unwrap());
+            assert!(res.is_ok(), "{}", res.err().unwrap());
             assert_eq!(res.unwrap(), vec![b'0' + bs[1]]);
             let s = format!("%{{2}}%{{1}}%{}%d", op);
             let res = expand(s.as_bytes(), &[], &mut Variables::new());
-            assert!(res.is_ok(), res.err().unwrap());
+            assert!(res.is_ok(), "{}", res.err().unwrap());
             assert_eq!(res.unwrap(), vec![b'0' + bs[2]]);
         }
     }
@@ -691,13 +1677,13 @@ mod test {
         let mut vars = Variables::new();
         let s = b"\\E[%?%p1%{8}%<%t3%p1%d%e%p1%{16}%<%t9%p1%{8}%-%d%e38;5;%p1%d%;m";
         let res = expand(s, &[Number(1)], &mut vars);
-        assert!(res.is_ok(), res.err().unwrap());
+        assert!(res.is_ok(), "{}", res.err().unwrap());
         assert_eq!(res.unwrap(), "\\E[31m".bytes().collect::<Vec<_>>());
         let res = expand(s, &[Number(8)], &mut vars);
-        assert!(res.is_ok(), res.err().unwrap());
+        assert!(res.is_ok(), "{}", res.err().unwrap());
         assert_eq!(res.unwrap(), "\\E[90m".bytes().collect::<Vec<_>>());
         let res = expand(s, &[Number(42)], &mut vars);
-        assert!(res.is_ok(), res.err().unwrap());
+        assert!(res.is_ok(), "{}", res.err().unwrap());
         assert_eq!(res.unwrap(), "\\E[38;5;42m".bytes().collect::<Vec<_>>());
     }
 
@@ -721,5 +1707,145 @@ mod test {
                           &[Number(15), Number(27)],
                           vars),
                    Ok("17017  001b0X001B".bytes().collect::<Vec<_>>()));
+        assert_eq!(expand(b"%p1%.3d|%p1%:+5.3d|%p1% d|%p1%o|%p2%#x|%p2%#o|%p3%d",
+                          &[Number(-7), Number(0), Number(i32::MIN)],
+                          vars),
+                   Ok("-007|  -07|-7|37777777771|0|00|-2147483648".bytes().collect::<Vec<_>>()));
+        assert_eq!(expand(b"%p1%:-40d|", &[Number(1)], vars),
+                   Ok(format!("1{}|", " ".repeat(39)).into_bytes()));
+    }
+
+    #[test]
+    fn test_expand_to() {
+        let cap = b"\\E[%p1%:-4d%p2%s%p1%c";
+        let params = [Number(65), Words("xy".to_owned())];
+        let mut out = b"abc".to_vec();
+        expand_to(cap, &params, &mut Variables::new(), &mut out).unwrap();
+        assert_eq!(out, b"abc\\E[65  xyA");
+        let mut out = Vec::new();
+        Program::compile(cap).unwrap().expand_to(&params, &mut Variables::new(), &mut out).unwrap();
+        assert_eq!(out, b"\\E[65  xyA");
+
+        let err = expand_to(b"%p1%s", &params, &mut Variables::new(), &mut Vec::new());
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let mut full = [0; 2];
+        let err = expand_to(cap, &params, &mut Variables::new(), &mut &mut full[..]);
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_expand_to_writes_literals_at_once() {
+        /// Records each write separately.
+        struct Writes(Vec<Vec<u8>>);
+
+        impl io::Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.push(buf.to_vec());
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut out = Writes(Vec::new());
+        expand_to(b"\\E[%p1%dmtext 100%% done", &[Number(1)], &mut Variables::new(), &mut out)
+            .unwrap();
+        assert_eq!(out.0, [&b"\\E["[..], b"1", b"mtext 100", b"% done"]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        use super::{analyze, Error, ParamType};
+
+        let setaf = analyze(b"\\E[%?%p1%{8}%<%t3%p1%d%e%p1%{16}%<%t9%p1%{8}%-%d%e38;5;%p1%d%;m");
+        assert_eq!(setaf.arity, 1);
+        assert_eq!(setaf.params[0], ParamType::Number);
+        assert_eq!(setaf.params[1], ParamType::Unused);
+        assert!(setaf.errors.is_empty());
+        assert!(setaf.accepts(&[Number(1)]));
+        assert!(!setaf.accepts(&[]));
+        assert!(!setaf.accepts(&[Words("red".to_owned())]));
+
+        let vars = analyze(b"%p2%Pa%p3%l%PZ%ga%s%gZ%gb%d");
+        assert_eq!(vars.arity, 3);
+        assert_eq!(vars.params[..3], [ParamType::Unused, ParamType::Words, ParamType::Words]);
+        assert_eq!(vars.reads, ['Z', 'a', 'b']);
+        assert_eq!(vars.writes, ['Z', 'a']);
+
+        assert_eq!(analyze(b"%p1%Pa").params[0], ParamType::Any);
+        assert_eq!(analyze(b"%p1%d%p1%s").params[0], ParamType::Mixed);
+        assert_eq!(analyze(b"%i").params[..2], [ParamType::Number, ParamType::Number]);
+
+        // Only the branch without a pushed value underflows.
+        let branches = analyze(b"%?%p1%t%{1}%;%d");
+        assert!(branches.can_underflow());
+        assert_eq!(branches.errors, [Error::StackUnderflow]);
+        assert_eq!(analyze(b"%{1}%s").errors, [Error::TypeMismatch]);
+        assert_eq!(analyze(b"%?%p1%t%Z%;").errors, [Error::UnrecognizedFormatOption('Z')]);
+        assert_eq!(analyze(b"%p1%d%Z").errors, [Error::UnrecognizedFormatOption('Z')]);
+    }
+
+    #[test]
+    fn test_termcap() {
+        use super::{expand_termcap, termcap_to_terminfo};
+
+        let expand = |cap: &[u8], params: &[i32]| {
+            let params: Vec<Param> = params.iter().map(|&n| Number(n)).collect();
+            expand_termcap(cap, &params, &mut Variables::new()).unwrap()
+        };
+        assert_eq!(termcap_to_terminfo(b"\\E[%i%d;%dH").unwrap(), b"\\E[%i%p1%d;%p2%dH");
+        assert_eq!(termcap_to_terminfo(b"\\E=%+ %+ ").unwrap(),
+                   b"\\E=%p1%' '%+%c%p2%' '%+%c");
+        assert_eq!(termcap_to_terminfo(b"%r%2%%%3").unwrap(), b"%p2%{100}%m%.2d%%%p1%{1000}%m%.3d");
+        assert_eq!(termcap_to_terminfo(b"%>ab%.").unwrap(),
+                   b"%p1%Pa%?%ga%'a'%>%t%ga%'b'%+%Pa%;%ga%c");
+
+        assert_eq!(expand(b"\x1b=%+ %+ ", &[5, 10]), b"\x1b=%*");
+        assert_eq!(expand(b"%r%d;%d", &[5, 10]), b"10;5");
+        assert_eq!(expand(b"%i%r%2,%3", &[5, 10]), b"11,006");
+        assert_eq!(expand(b"%>\x0a\x05%d,%>\x0a\x05%d", &[10, 11]), b"10,16");
+        assert_eq!(expand(b"%B%.%D%d", &[42, 20]), b"\x4212");
+        assert_eq!(expand(b"%n%.%.", &[1, 2]), b"ab");
+        assert_eq!(expand(b"%i%n%d", &[1, 2]), b"98");
+        assert_eq!(expand(b"%r%+ %r%d", &[1, 2]), b"\"34");
+
+        assert_eq!(termcap_to_terminfo(b"%d%x"), Err(super::Error::UnrecognizedFormatOption('x')));
+        assert!(termcap_to_terminfo(b"%d%d%d%d%d%d%d%d%d").is_ok());
+        assert_eq!(termcap_to_terminfo(b"%d%d%d%d%d%d%d%d%d%d"),
+                   Err(super::Error::TooManyParameters));
+        assert_eq!(termcap_to_terminfo(b"%d%>").unwrap(), b"%p1%d");
+        assert_eq!(termcap_to_terminfo(b"\\E%").unwrap(), b"\\E%");
+        assert_eq!(termcap_to_terminfo(b"%+\x1e%s").unwrap(), b"%p1%{30}%+%c%p2%s");
+    }
+
+    #[test]
+    fn test_program() {
+        let caps: &[&[u8]] = &[b"\\E[%i%p1%d;%p2%dH",
+                               b"%?%p1%t%p2%d%e%{5}%d%;x",
+                               b"%?%p1%tyes%ewhere%'%e'no%;",
+                               b"%?%p1%t%?%p2%ta%eb%;%ec%;d",
+                               b"%p1%{2}%/%Pa%ga%:-5d|%p2%s%p2%l%d",
+                               b"%?%p1%t%Z%;ok",
+                               b"%e%Z%;after",
+                               b"%'a",
+                               b"%{12",
+                               b"%p1%c%p2%c"];
+        let params = [[Number(0), Number(0)], [Number(2), Words("hi".to_owned())]];
+        for cap in caps {
+            let program = Program::compile(cap).unwrap();
+            for params in &params {
+                assert_eq!(program.expand(params, &mut Variables::new()),
+                           expand(cap, params, &mut Variables::new()),
+                           "{}",
+                           String::from_utf8_lossy(cap));
+            }
+        }
+        // The error is reported by `compile` if every expansion runs into it.
+        assert!(Program::compile(b"%p1%d%Z%?").is_err());
+        let program = Program::compile(b"%?%p1%t%Z%;").unwrap();
+        assert_eq!(program.expand(&[Number(1)], &mut Variables::new()),
+                   Err(super::Error::UnrecognizedFormatOption('Z')));
     }
 }
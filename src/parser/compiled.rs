@@ -10,161 +10,639 @@
 
 //! ncurses-compatible compiled terminfo format parsing (term(5))
 
-use std::collections::HashMap;
-use std::io::prelude::*;
-use std::io;
+use std::io::{self, Read};
+use std::str;
 
-use Error;
+use {Span, StringValue};
 use Terminfo;
+use {Error, ErrorKind, Section};
 
 pub use parser::names::*;
 
 // These are the orders ncurses uses in its compiled format (as of 5.9). Not
 // sure if portable.
 
-fn read_le_u16(r: &mut io::Read) -> io::Result<u16> {
-    let mut b = [0; 2];
-    let mut amt = 0;
-    while amt < b.len() {
-        match try!(r.read(&mut b[amt..])) {
-            0 => return Err(io::Error::new(io::ErrorKind::Other, "end of file")),
-            n => amt += n,
+/// Magic number of the legacy format, where numbers are 16-bit.
+const MAGIC_LEGACY: u16 = 0x011A;
+/// Magic number of the extended number format (ncurses 6.1+), where numbers are 32-bit.
+const MAGIC_32BIT: u16 = 0x021E;
+
+/// A cursor over a compiled entry.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    /// The section being read, for errors.
+    section: Section,
+}
+
+impl<'a> Reader<'a> {
+    /// An error at `offset` in the section being read.
+    fn error(&self, offset: usize, kind: ErrorKind) -> io::Error {
+        Error {
+            offset,
+            section: self.section,
+            kind,
+        }
+        .into()
+    }
+
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let bytes = match self.buf.get(self.pos..self.pos + n) {
+            Some(bytes) => bytes,
+            None => return Err(self.error(self.pos, ErrorKind::UnexpectedEof)),
+        };
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    /// Like `bytes`, but stops early at the end of the buffer.
+    fn bytes_up_to(&mut self, n: usize) -> &'a [u8] {
+        let bytes = &self.buf[self.pos..(self.pos + n).min(self.buf.len())];
+        self.pos += bytes.len();
+        bytes
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn le_u16(&mut self) -> io::Result<u16> {
+        Ok(le_u16(self.bytes(2)?, 0))
+    }
+
+    /// Read a section length from the header, given the name of the field.
+    ///
+    /// According to the spec, these fields must be >= -1 where -1 means that the feature is not
+    /// supported. Using 0 instead of -1 works because we skip sections with length 0.
+    fn nonneg(&mut self, field: &'static str) -> io::Result<usize> {
+        let pos = self.pos;
+        match self.le_u16()? as i16 {
+            n if n >= 0 => Ok(n as usize),
+            -1 => Ok(0),
+            _ => Err(self.error(pos, ErrorKind::InvalidLength(field))),
+        }
+    }
+
+    /// Check the string values just read, reporting errors with the capability names given by
+    /// `name`. The offsets start at `offsets_pos` in the entry, in `offsets_section`, and the
+    /// table at `table_pos`, in the current section.
+    fn check_strings(&self,
+                     offsets: &[u8],
+                     (offsets_pos, offsets_section): (usize, Section),
+                     table: &[u8],
+                     table_pos: usize,
+                     name: &dyn Fn(usize) -> String)
+                     -> io::Result<()> {
+        for i in 0..offsets.len() / 2 {
+            let offset = le_u16(offsets, i);
+            let (section, pos, kind) = match string_at(table, offset) {
+                Ok(_) => continue,
+                Err(StringError::OutOfRange) => {
+                    let kind = ErrorKind::InvalidStringOffset(name(i));
+                    (offsets_section, offsets_pos + 2 * i, kind)
+                }
+                Err(StringError::MissingNull) => {
+                    let kind = ErrorKind::StringsMissingNull(name(i));
+                    (self.section, table_pos + offset as usize, kind)
+                }
+            };
+            return Err(Error {
+                           offset: pos,
+                           section,
+                           kind,
+                       }
+                       .into());
         }
+        Ok(())
     }
-    Ok((b[0] as u16) | ((b[1] as u16) << 8))
 }
 
-fn read_byte(r: &mut io::Read) -> io::Result<u8> {
-    match r.bytes().next() {
-        Some(s) => s,
-        None => Err(io::Error::new(io::ErrorKind::Other, "end of file")),
+fn le_u16(buf: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([buf[2 * i], buf[2 * i + 1]])
+}
+
+/// The size of a number in the format selected by the magic number.
+fn number_size(wide: bool) -> usize {
+    if wide { 4 } else { 2 }
+}
+
+/// Decode the `i`th number of a numbers section.
+///
+/// Negative values mean the capability is absent (-1) or cancelled (-2).
+fn number(numbers: &[u8], wide: bool, i: usize) -> Option<u32> {
+    let n = if wide {
+        i32::from_le_bytes([numbers[4 * i],
+                            numbers[4 * i + 1],
+                            numbers[4 * i + 2],
+                            numbers[4 * i + 3]])
+    } else {
+        le_u16(numbers, i) as i16 as i32
+    };
+    if n >= 0 { Some(n as u32) } else { None }
+}
+
+/// Why a string couldn't be read from a string table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StringError {
+    /// The offset is past the end of the table.
+    OutOfRange,
+    /// The string runs past the end of the table.
+    MissingNull,
+}
+
+/// Find the NUL-terminated string starting at `offset` in a string table.
+fn read_string(table: &[u8], offset: usize) -> Result<&[u8], StringError> {
+    let tail = table.get(offset..).ok_or(StringError::OutOfRange)?;
+    match tail.iter().position(|&b| b == 0) {
+        Some(len) => Ok(&tail[..len]),
+        None => Err(StringError::MissingNull),
     }
 }
 
-/// Parse a compiled terminfo entry.
-pub fn parse(file: &mut io::Read) -> io::Result<Terminfo> {
-    // Check magic number
-    let magic = try!(read_le_u16(file));
-    if magic != 0x011A {
-        return Err(Error::BadMagic(magic).into());
-    }
-
-    // According to the spec, these fields must be >= -1 where -1 means that the
-    // feature is not
-    // supported. Using 0 instead of -1 works because we skip sections with length
-    // 0.
-    macro_rules! read_nonneg {
-        () => {{
-            match try!(read_le_u16(file)) as i16 {
-                n if n >= 0 => n as usize,
-                -1 => 0,
-                _ => return Err(Error::InvalidLength.into()),
+/// Look up a string value by its offset in a string table.
+fn string_at(table: &[u8], offset: u16) -> Result<Option<&[u8]>, StringError> {
+    match offset {
+        // non-entry
+        0xFFFF => Ok(None),
+        // undocumented: FFFE indicates cap@, which means the capability is not present
+        0xFFFE => Ok(None),
+        offset => read_string(table, offset as usize).map(Some),
+    }
+}
+
+/// The name of the `i`th standard capability of a type, given its known names and the prefix
+/// of its type.
+///
+/// Newer versions of ncurses may define more standard capabilities than `boolnames`,
+/// `numnames` and `stringnames` list. Those are known by their type and index, like `bool44`,
+/// `num39` and `str414`.
+fn standard_name(names: &[&str], prefix: &str, i: usize) -> String {
+    match names.get(i) {
+        Some(name) => name.to_string(),
+        None => format!("{}{}", prefix, i),
+    }
+}
+
+/// The index of an unknown standard capability, from a name given by `standard_name`.
+pub(crate) fn unknown_index(names: &[&str], prefix: &str, name: &str) -> Option<usize> {
+    let index = name.strip_prefix(prefix)?;
+    let i: usize = index.parse().ok().filter(|&i| i >= names.len())?;
+    // Only the canonical spelling, without a sign or leading zeros.
+    if i.to_string() == index { Some(i) } else { None }
+}
+
+/// The extended (user-defined) capabilities section that ncurses may append after the string
+/// table.
+#[derive(Debug, Clone, Copy, Default)]
+struct Extended<'a> {
+    bools: &'a [u8],
+    numbers: &'a [u8],
+    string_offsets: &'a [u8],
+    name_offsets: &'a [u8],
+    table: &'a [u8],
+    /// Where the names start in `table`; their offsets are relative to it.
+    names_base: usize,
+}
+
+impl<'a> Extended<'a> {
+    fn parse(r: &mut Reader<'a>, wide: bool) -> io::Result<Extended<'a>> {
+        let bools_count = r.nonneg("boolean count")?;
+        let numbers_count = r.nonneg("number count")?;
+        let strings_count = r.nonneg("string count")?;
+        // The number of valid entries in the string table, which we don't need.
+        let _ = r.nonneg("string table entries")?;
+        let string_table_bytes = r.nonneg("string table size")?;
+
+        let bools = r.bytes(bools_count)?;
+        if bools_count % 2 == 1 {
+            r.byte()?; // compensate for padding
+        }
+        let numbers = r.bytes(numbers_count * number_size(wide))?;
+        let offsets_pos = r.pos;
+        let string_offsets = r.bytes(strings_count * 2)?;
+        let name_offsets_pos = r.pos;
+        let name_offsets = r.bytes((bools_count + numbers_count + strings_count) * 2)?;
+        let table_pos = r.pos;
+        let table = r.bytes_up_to(string_table_bytes);
+
+        // The names follow the string values; their offsets are relative to the end of the last
+        // string value that is actually present. Invalid values are reported below.
+        let mut names_base = 0;
+        for i in 0..strings_count {
+            let offset = le_u16(string_offsets, i);
+            if let Ok(Some(s)) = string_at(table, offset) {
+                if (offset as i16) >= 0 {
+                    names_base = offset as usize + s.len() + 1;
+                }
+            }
+        }
+
+        let extended = Extended {
+            bools,
+            numbers,
+            string_offsets,
+            name_offsets,
+            table,
+            names_base,
+        };
+        let names_start = extended.len() - strings_count;
+        r.check_strings(string_offsets, (offsets_pos, Section::Extended), table, table_pos, &|i| {
+            extended.name(names_start + i).to_owned()
+        })?;
+        for i in 0..extended.len() {
+            let offset = le_u16(name_offsets, i) as usize;
+            let name = read_string(&table[names_base..], offset).map_err(|_| {
+                r.error(name_offsets_pos + 2 * i, ErrorKind::InvalidName(i))
+            })?;
+            if let Err(e) = str::from_utf8(name) {
+                let pos = table_pos + names_base + offset + e.valid_up_to();
+                return Err(r.error(pos, ErrorKind::NotUtf8(e)));
+            }
+        }
+        Ok(extended)
+    }
+
+    /// The number of capabilities, of all types.
+    fn len(&self) -> usize {
+        self.name_offsets.len() / 2
+    }
+
+    /// The name of the `i`th capability, counting booleans, then numbers, then strings.
+    fn name(&self, i: usize) -> &'a str {
+        let names = &self.table[self.names_base..];
+        read_string(names, le_u16(self.name_offsets, i) as usize)
+            .ok()
+            .and_then(|name| str::from_utf8(name).ok())
+            .unwrap_or_default()
+    }
+
+    fn find(&self, range: ::std::ops::Range<usize>, name: &str) -> Option<usize> {
+        let start = range.start;
+        range.into_iter().find(|&i| self.name(i) == name).map(|i| i - start)
+    }
+
+    fn string(&self, i: usize) -> Option<&'a [u8]> {
+        string_at(self.table, le_u16(self.string_offsets, i)).ok().and_then(|s| s)
+    }
+}
+
+/// A compiled terminfo entry, read in place from a byte buffer.
+///
+/// This validates the entry like `parse` does, but string values are borrowed from the buffer
+/// instead of being copied.
+#[derive(Debug, Clone, Copy)]
+pub struct TerminfoRef<'a> {
+    names: &'a str,
+    wide: bool,
+    bools: &'a [u8],
+    numbers: &'a [u8],
+    string_offsets: &'a [u8],
+    string_table: &'a [u8],
+    extended: Extended<'a>,
+}
+
+impl<'a> TerminfoRef<'a> {
+    /// Parse a compiled terminfo entry.
+    ///
+    /// Both the legacy format (magic `0x11A`) and the ncurses 6.1+ format with 32-bit numbers
+    /// (magic `0x21E`) are supported.
+    pub fn parse(buf: &'a [u8]) -> io::Result<TerminfoRef<'a>> {
+        let r = &mut Reader {
+            buf,
+            pos: 0,
+            section: Section::Header,
+        };
+
+        // Check magic number
+        let magic = r.le_u16()?;
+        let wide = match magic {
+            MAGIC_LEGACY => false,
+            MAGIC_32BIT => true,
+            _ => return Err(r.error(0, ErrorKind::BadMagic(magic))),
+        };
+
+        let names_bytes = r.nonneg("names size")?;
+        let bools_bytes = r.nonneg("boolean count")?;
+        let numbers_count = r.nonneg("number count")?;
+        let string_offsets_count = r.nonneg("string count")?;
+        let string_table_bytes = r.nonneg("string table size")?;
+
+        r.section = Section::Names;
+        if names_bytes == 0 {
+            return Err(r.error(r.pos, ErrorKind::ShortNames));
+        }
+
+        // don't read NUL
+        let names_pos = r.pos;
+        let names = str::from_utf8(r.bytes(names_bytes - 1)?).map_err(|e| {
+            r.error(names_pos + e.valid_up_to(), ErrorKind::NotUtf8(e))
+        })?;
+        // consume NUL
+        if r.byte()? != b'\0' {
+            return Err(r.error(r.pos - 1, ErrorKind::NamesMissingNull));
+        }
+
+        r.section = Section::Bools;
+        let bools = r.bytes(bools_bytes)?;
+        if (bools_bytes + names_bytes) % 2 == 1 {
+            r.byte()?; // compensate for padding
+        }
+
+        r.section = Section::Numbers;
+        let numbers = r.bytes(numbers_count * number_size(wide))?;
+        r.section = Section::StringOffsets;
+        let offsets_pos = r.pos;
+        let string_offsets = r.bytes(string_offsets_count * 2)?;
+        r.section = Section::StringTable;
+        let table_pos = r.pos;
+        let string_table = r.bytes_up_to(string_table_bytes);
+        r.check_strings(string_offsets,
+                        (offsets_pos, Section::StringOffsets),
+                        string_table,
+                        table_pos,
+                        &|i| standard_name(stringnames, "str", i))?;
+
+        // The extended section starts on an even boundary, if it is present at all.
+        if string_table_bytes % 2 == 1 && !r.at_end() {
+            r.byte()?;
+        }
+        r.section = Section::Extended;
+        let extended = if r.at_end() {
+            Extended::default()
+        } else {
+            Extended::parse(r, wide)?
+        };
+
+        Ok(TerminfoRef {
+            names,
+            wide,
+            bools,
+            numbers,
+            string_offsets,
+            string_table,
+            extended,
+        })
+    }
+
+    /// Names for the terminal.
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + 'a {
+        self.names.split('|')
+    }
+
+    /// Look up a boolean capability, standard or extended, by name.
+    pub fn bool(&self, name: &str) -> bool {
+        let ext = &self.extended;
+        match BoolCap::from_name(name) {
+            Some(cap) => self.bools.get(cap.index()) == Some(&1),
+            None => {
+                ext.find(0..ext.bools.len(), name).is_some_and(|i| ext.bools[i] == 1) ||
+                unknown_index(boolnames, "bool", name).is_some_and(|i| {
+                    self.bools.get(i) == Some(&1)
+                })
             }
-        }}
+        }
     }
 
-    let names_bytes = read_nonneg!();
-    let bools_bytes = read_nonneg!();
-    let numbers_count = read_nonneg!();
-    let string_offsets_count = read_nonneg!();
-    let string_table_bytes = read_nonneg!();
+    /// Look up a numeric capability, standard or extended, by name.
+    pub fn number(&self, name: &str) -> Option<u32> {
+        match NumberCap::from_name(name) {
+            Some(cap) => self.standard_number(cap.index()),
+            None => {
+                let ext = &self.extended;
+                let start = ext.bools.len();
+                let count = ext.numbers.len() / number_size(self.wide);
+                ext.find(start..start + count, name)
+                   .and_then(|i| number(ext.numbers, self.wide, i))
+                   .or_else(|| {
+                       unknown_index(numnames, "num", name).and_then(|i| self.standard_number(i))
+                   })
+            }
+        }
+    }
 
-    if names_bytes == 0 {
-        return Err(Error::ShortNames.into());
+    /// Look up the raw (unexpanded) value of a string capability, standard or extended, by name.
+    ///
+    /// Cancelled strings are reported as absent.
+    pub fn string(&self, name: &str) -> Option<&'a [u8]> {
+        match StringCap::from_name(name) {
+            Some(cap) => self.standard_string(cap.index()),
+            None => {
+                let ext = &self.extended;
+                let start = ext.len() - ext.string_offsets.len() / 2;
+                ext.find(start..ext.len(), name).and_then(|i| ext.string(i)).or_else(|| {
+                    unknown_index(stringnames, "str", name).and_then(|i| self.standard_string(i))
+                })
+            }
+        }
     }
 
-    if bools_bytes > boolnames.len() {
-        return Err(Error::TooManyBools.into());
+    /// Names of the boolean capabilities that are set, standard ones first.
+    ///
+    /// Standard capabilities unknown to this crate are left out; see `unknown_bools`.
+    pub fn bools(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let this = *self;
+        let ext = self.extended;
+        (0..self.bools.len().min(boolnames.len()))
+            .filter(move |&i| this.bools[i] == 1)
+            .map(|i| boolnames[i])
+            .chain((0..ext.bools.len())
+                       .filter(move |&i| ext.bools[i] == 1)
+                       .map(move |i| ext.name(i)))
     }
 
-    if numbers_count > numnames.len() {
-        return Err(Error::TooManyNumbers.into());
+    /// The numeric capabilities, standard ones first.
+    ///
+    /// Standard capabilities unknown to this crate are left out; see `unknown_numbers`.
+    pub fn numbers(&self) -> impl Iterator<Item = (&'a str, u32)> + 'a {
+        let this = *self;
+        let ext = self.extended;
+        let wide = self.wide;
+        (0..numnames.len())
+            .filter_map(move |i| this.standard_number(i).map(|n| (numnames[i], n)))
+            .chain((0..ext.numbers.len() / number_size(wide)).filter_map(move |i| {
+                number(ext.numbers, wide, i).map(|n| (ext.name(ext.bools.len() + i), n))
+            }))
     }
 
-    if string_offsets_count > stringnames.len() {
-        return Err(Error::TooManyStrings.into());
+    /// The raw string capabilities, standard ones first.
+    ///
+    /// Cancelled strings and standard capabilities unknown to this crate are left out; see
+    /// `cancelled_strings` and `unknown_strings`.
+    pub fn strings(&self) -> impl Iterator<Item = (&'a str, &'a [u8])> + 'a {
+        let this = *self;
+        let ext = self.extended;
+        let names_start = ext.len() - ext.string_offsets.len() / 2;
+        (0..stringnames.len())
+            .filter_map(move |i| this.standard_string(i).map(|s| (stringnames[i], s)))
+            .chain((0..ext.string_offsets.len() / 2).filter_map(move |i| {
+                ext.string(i).map(|s| (ext.name(names_start + i), s))
+            }))
     }
 
-    // don't read NUL
-    let mut bytes = Vec::new();
-    try!(file.take((names_bytes - 1) as u64).read_to_end(&mut bytes));
-    let names_str = try!(String::from_utf8(bytes).map_err(Error::from));
+    /// Names of the string capabilities that are cancelled, standard ones first.
+    pub fn cancelled_strings(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let offsets = self.string_offsets;
+        let ext = self.extended;
+        let names_start = ext.len() - ext.string_offsets.len() / 2;
+        (0..stringnames.len().min(offsets.len() / 2))
+            .filter(move |&i| le_u16(offsets, i) == 0xFFFE)
+            .map(|i| stringnames[i])
+            .chain((0..ext.string_offsets.len() / 2)
+                       .filter(move |&i| le_u16(ext.string_offsets, i) == 0xFFFE)
+                       .map(move |i| ext.name(names_start + i)))
+    }
 
-    let term_names: Vec<String> = names_str.split('|')
-                                           .map(|s| s.to_owned())
-                                           .collect();
-    // consume NUL
-    if try!(read_byte(file)) != b'\0' {
-        return Err(Error::NamesMissingNull.into());
+    /// The indices of the standard booleans that are set but unknown to this crate, because
+    /// they come after `boolnames` in an entry from a newer ncurses.
+    ///
+    /// These can also be looked up with `bool` by names like `bool44`.
+    pub fn unknown_bools(&self) -> impl Iterator<Item = usize> + 'a {
+        let bools = self.bools;
+        (boolnames.len()..bools.len()).filter(move |&i| bools[i] == 1)
     }
 
-    let bools_map: HashMap<&str, bool> = try! {
-        (0..bools_bytes).filter_map(|i| match read_byte(file) {
-            Err(e) => Some(Err(e)),
-            Ok(1) => Some(Ok((boolnames[i], true))),
-            Ok(_) => None
-        }).collect()
-    };
+    /// The standard numeric capabilities unknown to this crate, by index; see `unknown_bools`.
+    ///
+    /// These can also be looked up with `number` by names like `num39`.
+    pub fn unknown_numbers(&self) -> impl Iterator<Item = (usize, u32)> + 'a {
+        let this = *self;
+        (numnames.len()..self.numbers.len() / number_size(self.wide))
+            .filter_map(move |i| this.standard_number(i).map(|n| (i, n)))
+    }
 
-    if (bools_bytes + names_bytes) % 2 == 1 {
-        try!(read_byte(file)); // compensate for padding
+    /// The standard string capabilities unknown to this crate, by index; see `unknown_bools`.
+    ///
+    /// These can also be looked up with `string` by names like `str414`.
+    pub fn unknown_strings(&self) -> impl Iterator<Item = (usize, &'a [u8])> + 'a {
+        let this = *self;
+        (stringnames.len()..self.string_offsets.len() / 2)
+            .filter_map(move |i| this.standard_string(i).map(|s| (i, s)))
     }
 
-    let numbers_map: HashMap<&str, u16> = try! {
-        (0..numbers_count).filter_map(|i| match read_le_u16(file) {
-            Ok(0xFFFF) => None,
-            Ok(n) => Some(Ok((numnames[i], n))),
-            Err(e) => Some(Err(e))
-        }).collect()
-    };
+    /// Copy the entry into a `Terminfo`.
+    pub fn to_terminfo(&self) -> Terminfo {
+        let mut info = Terminfo::new(self.names().map(|s| s.to_owned()).collect());
+        for i in (0..self.bools.len().min(boolnames.len())).filter(|&i| self.bools[i] == 1) {
+            info.bools |= 1 << i;
+        }
+        info.unknown_bools.extend(self.unknown_bools());
+        for (i, n) in info.numbers.iter_mut().enumerate() {
+            *n = self.standard_number(i);
+        }
+        info.unknown_numbers.extend(self.unknown_numbers());
 
-    let string_map: HashMap<&str, Vec<u8>> = if string_offsets_count > 0 {
-        let string_offsets: Vec<u16> = try!((0..string_offsets_count)
-                                                .map(|_| read_le_u16(file))
-                                                .collect());
-
-        let mut string_table = Vec::new();
-        try!(file.take(string_table_bytes as u64).read_to_end(&mut string_table));
-
-        try!(string_offsets.into_iter()
-                           .enumerate()
-                           .filter(|&(_, offset)| {
-                               // non-entry
-                               offset != 0xFFFF
-                           })
-                           .map(|(i, offset)| {
-                               let offset = offset as usize;
-
-                               let name = stringnames[i];
-
-                               if offset == 0xFFFE {
-                                   // undocumented: FFFE indicates cap@, which means the capability
-                                   // is not present
-                                   // unsure if the handling for this is correct
-                                   return Ok((name, Vec::new()));
-                               }
-
-                               // Find the offset of the NUL we want to go to
-                               let nulpos = string_table[offset..string_table_bytes]
-                                                .iter()
-                                                .position(|&b| b == 0);
-                               match nulpos {
-                                   Some(len) => {
-                                       Ok((name, string_table[offset..offset + len].to_vec()))
-                                   }
-                                   None => return Err(Error::StringsMissingNull),
-                               }
-                           })
-                           .collect())
-    } else {
-        HashMap::new()
-    };
+        // Standard strings keep their offsets in the copied table.
+        info.table.extend_from_slice(self.string_table);
+        for i in 0..self.string_offsets.len() / 2 {
+            let value = match le_u16(self.string_offsets, i) {
+                0xFFFF => None,
+                0xFFFE => Some(StringValue::Cancelled),
+                offset => {
+                    self.standard_string(i).map(|s| {
+                        StringValue::Value(Span {
+                                               start: offset as u32,
+                                               end: offset as u32 + s.len() as u32,
+                                           })
+                    })
+                }
+            };
+            if i < stringnames.len() {
+                info.strings[i] = value;
+            } else if let Some(value) = value {
+                info.unknown_strings.push((i, value));
+            }
+        }
+
+        let ext = &self.extended;
+        for i in 0..ext.bools.len() {
+            if ext.bools[i] == 1 {
+                info.set_bool(ext.name(i), true);
+            }
+        }
+        for i in 0..ext.numbers.len() / number_size(self.wide) {
+            if let Some(n) = number(ext.numbers, self.wide, i) {
+                info.set_number(ext.name(ext.bools.len() + i), Some(n));
+            }
+        }
+        let names_start = ext.len() - ext.string_offsets.len() / 2;
+        for i in 0..ext.string_offsets.len() / 2 {
+            let name = ext.name(names_start + i);
+            if let Some(s) = ext.string(i) {
+                let span = info.push_string(s);
+                info.set_value(name, Some(StringValue::Value(span)));
+            } else if le_u16(ext.string_offsets, i) == 0xFFFE {
+                info.cancel_string(name);
+            }
+        }
+        info
+    }
+
+    fn standard_number(&self, i: usize) -> Option<u32> {
+        if i < self.numbers.len() / number_size(self.wide) {
+            number(self.numbers, self.wide, i)
+        } else {
+            None
+        }
+    }
 
-    // And that's all there is to it
-    Ok(Terminfo {
-        names: term_names,
-        bools: bools_map,
-        numbers: numbers_map,
-        strings: string_map,
-    })
+    fn standard_string(&self, i: usize) -> Option<&'a [u8]> {
+        if i < self.string_offsets.len() / 2 {
+            string_at(self.string_table, le_u16(self.string_offsets, i)).ok().and_then(|s| s)
+        } else {
+            None
+        }
+    }
+}
+
+/// Decode the five section sizes of a header, reading absent and invalid sizes as 0; the parser
+/// reports invalid ones.
+fn header_sizes(header: &[u8]) -> [usize; 5] {
+    let mut sizes = [0; 5];
+    for (i, size) in sizes.iter_mut().enumerate() {
+        if let Some(&[lo, hi]) = header.get(2 * i..2 * i + 2) {
+            *size = i16::from_le_bytes([lo, hi]).max(0) as usize;
+        }
+    }
+    sizes
+}
+
+/// Read from `file` until `buf` holds `len` bytes or the file ends.
+fn fill_to(file: &mut dyn io::Read, buf: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    let n = len.saturating_sub(buf.len());
+    file.take(n as u64).read_to_end(buf).map(|_| ())
+}
+
+/// Parse a compiled terminfo entry.
+///
+/// Both the legacy format (magic `0x11A`) and the ncurses 6.1+ format with 32-bit numbers (magic
+/// `0x21E`) are supported.
+///
+/// Only the sizes given by the headers are read, leaving `file` just past the entry. Like
+/// ncurses, anything that follows the string table is taken to be an extended section, so an
+/// entry without one must end the file.
+pub fn parse(file: &mut dyn io::Read) -> io::Result<Terminfo> {
+    let mut buf = Vec::new();
+    fill_to(file, &mut buf, 12)?;
+    let wide = buf.starts_with(&MAGIC_32BIT.to_le_bytes());
+    let [names, bools, numbers, strings, table] = header_sizes(buf.get(2..).unwrap_or_default());
+    let len = 12 + names + bools + (names + bools) % 2 + numbers * number_size(wide) +
+              strings * 2 + table;
+    fill_to(file, &mut buf, len)?;
+
+    // The extended section starts on an even boundary, with its own header.
+    if buf.len() == len {
+        let start = len + table % 2;
+        fill_to(file, &mut buf, start + 10)?;
+        if buf.len() == start + 10 {
+            let [bools, numbers, strings, _, table] = header_sizes(&buf[start..]);
+            let len = bools + bools % 2 + numbers * number_size(wide) +
+                      (bools + numbers + 2 * strings) * 2 + table;
+            fill_to(file, &mut buf, start + 10 + len)?;
+        }
+    }
+    TerminfoRef::parse(&buf).map(|entry| entry.to_terminfo())
 }
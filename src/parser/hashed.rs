@@ -0,0 +1,384 @@
+//! Berkeley DB 1.85 hashed terminfo database reading
+//!
+//! Some ncurses builds store the whole database in a single `terminfo.db` hash file instead of
+//! a directory tree. Each entry is stored under its full names field (e.g.
+//! `xterm|xterm terminal emulator (X Window System)`) with a leading 0 byte before the compiled
+//! entry, and each individual name maps to a record holding a leading 2 byte and the full names
+//! field.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {Source, Terminfo};
+use parser::compiled;
+
+/// The magic number of a hash file (stored big-endian, like the rest of the header).
+const HASH_MAGIC: u32 = 0x061561;
+
+/// Byte order markers of the pages.
+const LITTLE_ENDIAN: u32 = 1234;
+const BIG_ENDIAN: u32 = 4321;
+
+/// Markers in the data offset slot of a page.
+const OVFLPAGE: u16 = 0;
+const PARTIAL_KEY: u16 = 1;
+const FULL_KEY: u16 = 2;
+const FULL_KEY_DATA: u16 = 3;
+const REAL_KEY: u16 = 4;
+
+/// The number of bits of an overflow address that hold the page number.
+const SPLITSHIFT: u32 = 11;
+
+/// The type bytes ncurses puts in front of the records.
+const ENTRY_RECORD: u8 = 0;
+const ALIAS_RECORD: u8 = 2;
+
+fn corrupt(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Chris Torek's hash function, the default of Berkeley DB 1.85.
+fn hash(key: &[u8]) -> u32 {
+    key.iter().fold(0u32, |h, &b| h.wrapping_mul(33).wrapping_add(b as u32))
+}
+
+/// The ceiling of the base 2 logarithm of `n`.
+fn log2(n: u32) -> u32 {
+    (0..32).find(|&i| 1u64 << i >= n as u64).unwrap_or(32)
+}
+
+/// A Berkeley DB 1.85 hash file.
+#[derive(Debug, Clone)]
+pub struct HashedDatabase {
+    path: Option<PathBuf>,
+    data: Vec<u8>,
+    little_endian: bool,
+    bsize: usize,
+    max_bucket: u32,
+    high_mask: u32,
+    low_mask: u32,
+    hdrpages: u32,
+    spares: [u32; 32],
+}
+
+/// The 16-bit words of a page.
+struct Page<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Page<'a> {
+    fn get(&self, i: usize) -> io::Result<u16> {
+        let b = self.bytes.get(2 * i..2 * i + 2).ok_or_else(|| corrupt("page index out of range"))?;
+        Ok(if self.little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        })
+    }
+
+    fn slice(&self, start: u16, end: usize) -> io::Result<&'a [u8]> {
+        self.bytes.get(start as usize..end).ok_or_else(|| corrupt("page offset out of range"))
+    }
+
+    /// The number of key and data offsets on the page.
+    fn len(&self) -> io::Result<usize> {
+        self.get(0).map(|n| n as usize)
+    }
+
+    /// The amount of free space on the page.
+    fn free_space(&self) -> io::Result<u16> {
+        self.get(self.len()? + 1)
+    }
+
+    /// The overflow page a big key/data pair continues on.
+    fn next(&self) -> io::Result<u16> {
+        let n = self.len()?;
+        if n < 2 {
+            return Err(corrupt("big key/data pair without a next page"));
+        }
+        self.get(n - 1)
+    }
+}
+
+impl HashedDatabase {
+    /// Read a hash file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<HashedDatabase> {
+        let mut db = HashedDatabase::parse(fs::read(path.as_ref())?)?;
+        db.path = Some(path.as_ref().to_path_buf());
+        Ok(db)
+    }
+
+    /// Check whether `path` looks like a hash file.
+    pub fn is_hashed<P: AsRef<Path>>(path: P) -> bool {
+        let mut magic = [0; 4];
+        fs::File::open(path)
+            .and_then(|mut file| io::Read::read_exact(&mut file, &mut magic))
+            .is_ok() && u32::from_be_bytes(magic) == HASH_MAGIC
+    }
+
+    /// Parse the contents of a hash file.
+    pub fn parse(data: Vec<u8>) -> io::Result<HashedDatabase> {
+        // The header consists of big-endian 32-bit fields.
+        let field = |i: usize| -> io::Result<u32> {
+            data.get(4 * i..4 * i + 4)
+                .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| corrupt("hash file header too short"))
+        };
+        if field(0)? != HASH_MAGIC {
+            return Err(corrupt("not a Berkeley DB hash file"));
+        }
+        match field(1)? {
+            1 | 2 => (),
+            _ => return Err(corrupt("unsupported hash file version")),
+        }
+        let little_endian = match field(2)? {
+            LITTLE_ENDIAN => true,
+            BIG_ENDIAN => false,
+            _ => return Err(corrupt("invalid hash file byte order")),
+        };
+        let bsize = field(3)? as usize;
+        if !bsize.is_power_of_two() || !(256..=65536).contains(&bsize) {
+            return Err(corrupt("invalid hash file bucket size"));
+        }
+        let mut spares = [0; 32];
+        for (i, spare) in spares.iter_mut().enumerate() {
+            *spare = field(17 + i)?;
+        }
+        let db = HashedDatabase {
+            path: None,
+            little_endian,
+            bsize,
+            max_bucket: field(10)?,
+            high_mask: field(11)?,
+            low_mask: field(12)?,
+            hdrpages: field(15)?,
+            spares,
+            data,
+        };
+
+        // The buckets are split in order, so the last one lies between the two masks, and each
+        // bucket has a page of its own.
+        let masks_valid = db.high_mask.checked_add(1).is_some_and(u32::is_power_of_two) &&
+                          db.low_mask == db.high_mask >> 1;
+        if !masks_valid ||
+           !(db.low_mask..=db.high_mask).contains(&db.max_bucket) {
+            return Err(corrupt("invalid hash file bucket masks"));
+        }
+        if db.bucket_to_page(db.max_bucket)? as usize >= db.data.len().div_ceil(bsize) {
+            return Err(corrupt("hash file has fewer pages than buckets"));
+        }
+        Ok(db)
+    }
+
+    fn bucket_to_page(&self, bucket: u32) -> io::Result<u32> {
+        let spares = if bucket == 0 {
+            0
+        } else {
+            let n = bucket.checked_add(1).ok_or_else(|| corrupt("bucket out of range"))?;
+            self.spares[(log2(n) as usize - 1).min(31)]
+        };
+        bucket.checked_add(self.hdrpages)
+              .and_then(|page| page.checked_add(spares))
+              .ok_or_else(|| corrupt("page number out of range"))
+    }
+
+    fn overflow_to_page(&self, addr: u16) -> io::Result<u32> {
+        let split = (addr as u32 >> SPLITSHIFT).min(31);
+        let page = addr as u32 & ((1 << SPLITSHIFT) - 1);
+        self.bucket_to_page((1 << split) - 1)?
+            .checked_add(page)
+            .ok_or_else(|| corrupt("page number out of range"))
+    }
+
+    /// Pages past the end of the file have never been written, and are empty.
+    fn page(&self, number: u32) -> Page<'_> {
+        let start = (number as usize).saturating_mul(self.bsize);
+        let bytes = self.data.get(start..start.saturating_add(self.bsize)).unwrap_or(&[]);
+        Page {
+            bytes: if bytes.len() == self.bsize { bytes } else { &[0, 0] },
+            little_endian: self.little_endian,
+        }
+    }
+
+    /// Call `f` with every key/data pair in `bucket` until it returns `true`.
+    fn walk_bucket(&self,
+                   bucket: u32,
+                   f: &mut dyn FnMut(Vec<u8>, Vec<u8>) -> bool)
+                   -> io::Result<bool> {
+        let mut number = self.bucket_to_page(bucket)?;
+        // Bound the chain length in case of a loop in a corrupt file.
+        for _ in 0..self.data.len() / self.bsize + 1 {
+            let page = self.page(number);
+            let n = page.len()?;
+            if n == 0 {
+                return Ok(false);
+            }
+            // Big pairs take up pages of their own.
+            let marker = page.get(2)?;
+            if marker < REAL_KEY && marker != OVFLPAGE {
+                let (key, data, last) = self.big_pair(number)?;
+                if f(key, data) {
+                    return Ok(true);
+                }
+                let last = self.page(last);
+                if last.len()? <= 2 {
+                    return Ok(false);
+                }
+                number = self.overflow_to_page(last.get(3)?)?;
+                continue;
+            }
+
+            let mut end = self.bsize;
+            let mut next = None;
+            for ndx in (1..n).step_by(2) {
+                let key_offset = page.get(ndx)?;
+                let data_offset = page.get(ndx + 1)?;
+                match data_offset {
+                    OVFLPAGE => {
+                        next = Some(key_offset);
+                        break;
+                    }
+                    PARTIAL_KEY | FULL_KEY | FULL_KEY_DATA => {
+                        return Err(corrupt("big key/data pair in the middle of a page"))
+                    }
+                    _ => (),
+                }
+                let key = page.slice(key_offset, end)?;
+                let data = page.slice(data_offset, key_offset as usize)?;
+                if f(key.to_vec(), data.to_vec()) {
+                    return Ok(true);
+                }
+                end = data_offset as usize;
+            }
+            match next {
+                Some(addr) => number = self.overflow_to_page(addr)?,
+                None => return Ok(false),
+            }
+        }
+        Err(corrupt("overflow page loop"))
+    }
+
+    /// Read a key/data pair too big to fit on one page, starting on page `number`.
+    ///
+    /// Returns the key, the data and the number of the last page of the pair.
+    fn big_pair(&self, mut number: u32) -> io::Result<(Vec<u8>, Vec<u8>, u32)> {
+        let limit = self.data.len() / self.bsize + 1;
+        let mut key = Vec::new();
+        let mut data = Vec::new();
+
+        // The key fills the ends of pages, the first few bytes on the first page.
+        let mut pages = 0..limit;
+        let mut page = self.page(number);
+        loop {
+            if pages.next().is_none() {
+                return Err(corrupt("overflow page loop"));
+            }
+            let offset = page.get(1)?;
+            key.extend_from_slice(page.slice(offset, self.bsize)?);
+            if page.get(2)? != PARTIAL_KEY {
+                break;
+            }
+            number = self.overflow_to_page(page.next()?)?;
+            page = self.page(number);
+        }
+
+        match page.get(2)? {
+            FULL_KEY => (),
+            FULL_KEY_DATA => {
+                // The data starts on the page the key ends on, right before the key.
+                let start = page.get(page.len()?)?;
+                data.extend_from_slice(page.slice(start, page.get(1)? as usize)?);
+                // If there is free space left, the data is complete.
+                if page.free_space()? != 0 {
+                    return Ok((key, data, number));
+                }
+            }
+            _ => return Err(corrupt("invalid big key/data pair")),
+        }
+
+        // The rest of the data fills the ends of the following pages.
+        loop {
+            if pages.next().is_none() {
+                return Err(corrupt("overflow page loop"));
+            }
+            number = self.overflow_to_page(page.next()?)?;
+            page = self.page(number);
+            let offset = page.get(1)?;
+            data.extend_from_slice(page.slice(offset, self.bsize)?);
+            match page.get(2)? {
+                FULL_KEY => (),
+                FULL_KEY_DATA => return Ok((key, data, number)),
+                _ => return Err(corrupt("invalid big key/data pair")),
+            }
+        }
+    }
+
+    /// Look up the data stored under `key`.
+    pub fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let mut bucket = hash(key) & self.high_mask;
+        if bucket > self.max_bucket {
+            bucket &= self.low_mask;
+        }
+        let mut found = None;
+        self.walk_bucket(bucket, &mut |k, data| {
+            if k == key {
+                found = Some(data);
+                true
+            } else {
+                false
+            }
+        })?;
+        Ok(found)
+    }
+
+    /// Every key/data pair in the file.
+    pub fn pairs(&self) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut pairs = Vec::new();
+        for bucket in 0..=self.max_bucket {
+            self.walk_bucket(bucket, &mut |key, data| {
+                pairs.push((key, data));
+                false
+            })?;
+        }
+        Ok(pairs)
+    }
+
+    /// The names fields (e.g. `xterm|xterm terminal emulator (X Window System)`) of all entries.
+    pub fn names(&self) -> io::Result<Vec<String>> {
+        Ok(self.pairs()?
+               .into_iter()
+               .filter(|(_, data)| data.first() == Some(&ENTRY_RECORD))
+               .filter_map(|(key, _)| String::from_utf8(key).ok())
+               .collect())
+    }
+
+    /// Look up a terminal by any of its names.
+    ///
+    /// Entries of a database read with `open` record its path as their source.
+    pub fn entry(&self, name: &str) -> io::Result<Option<Terminfo>> {
+        let mut key = name.as_bytes().to_vec();
+        // An alias record points to the entry record; ncurses gives up after three lookups.
+        for _ in 0..3 {
+            let data = match self.get(&key)? {
+                Some(data) => data,
+                None => return Ok(None),
+            };
+            match data.split_first() {
+                Some((&ENTRY_RECORD, entry)) => {
+                    let mut info = compiled::parse(&mut &entry[..])?;
+                    if let Some(ref path) = self.path {
+                        info.source = Source::HashedDatabase(path.clone());
+                    }
+                    return Ok(Some(info));
+                }
+                Some((&ALIAS_RECORD, names)) => {
+                    key = names.split(|&b| b == 0).next().unwrap_or(names).to_vec()
+                }
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+}
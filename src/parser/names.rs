@@ -1,96 +1,1252 @@
-#![allow(non_upper_case_globals, missing_docs)]
+#![allow(non_upper_case_globals)]
 #![cfg_attr(rustfmt, rustfmt_skip)]
 
-pub static boolnames: &'static [&'static str] = &["bw", "am", "xsb", "xhp", "xenl", "eo", "gn",
-                                                  "hc", "km", "hs", "in", "db", "da", "mir",
-                                                  "msgr", "os", "eslok", "xt", "hz", "ul", "xon",
-                                                  "nxon", "mc5i", "chts", "nrrmc", "npc", "ndscr",
-                                                  "ccc", "bce", "hls", "xhpa", "crxm", "daisy",
-                                                  "xvpa", "sam", "cpix", "lpix", "OTbs", "OTns",
-                                                  "OTnc", "OTMT", "OTNL", "OTpt", "OTxr"];
-
-pub static numnames: &'static [&'static str] = &["cols", "it", "lines", "lm", "xmc", "pb", "vt",
-                                                 "wsl", "nlab", "lh", "lw", "ma", "wnum",
-                                                 "colors", "pairs", "ncv", "bufsz", "spinv",
-                                                 "spinh", "maddr", "mjump", "mcs", "mls", "npins",
-                                                 "orc", "orl", "orhi", "orvi", "cps", "widcs",
-                                                 "btns", "bitwin", "bitype", "UTug", "OTdC",
-                                                 "OTdN", "OTdB", "OTdT", "OTkn"];
-
-pub static stringnames: &'static [&'static str] = &["cbt", "bel", "cr", "csr", "tbc", "clear",
-                                                    "el", "ed", "hpa", "cmdch", "cup", "cud1",
-                                                    "home", "civis", "cub1", "mrcup", "cnorm",
-                                                    "cuf1", "ll", "cuu1", "cvvis", "dch1", "dl1",
-                                                    "dsl", "hd", "smacs", "blink", "bold",
-                                                    "smcup", "smdc", "dim", "smir", "invis",
-                                                    "prot", "rev", "smso", "smul", "ech", "rmacs",
-                                                    "sgr0", "rmcup", "rmdc", "rmir", "rmso",
-                                                    "rmul", "flash", "ff", "fsl", "is1", "is2",
-                                                    "is3", "if", "ich1", "il1", "ip", "kbs",
-                                                    "ktbc", "kclr", "kctab", "kdch1", "kdl1",
-                                                    "kcud1", "krmir", "kel", "ked", "kf0", "kf1",
-                                                    "kf10", "kf2", "kf3", "kf4", "kf5", "kf6",
-                                                    "kf7", "kf8", "kf9", "khome", "kich1", "kil1",
-                                                    "kcub1", "kll", "knp", "kpp", "kcuf1", "kind",
-                                                    "kri", "khts", "kcuu1", "rmkx", "smkx", "lf0",
-                                                    "lf1", "lf10", "lf2", "lf3", "lf4", "lf5",
-                                                    "lf6", "lf7", "lf8", "lf9", "rmm", "smm",
-                                                    "nel", "pad", "dch", "dl", "cud", "ich",
-                                                    "indn", "il", "cub", "cuf", "rin", "cuu",
-                                                    "pfkey", "pfloc", "pfx", "mc0", "mc4", "mc5",
-                                                    "rep", "rs1", "rs2", "rs3", "rf", "rc", "vpa",
-                                                    "sc", "ind", "ri", "sgr", "hts", "wind", "ht",
-                                                    "tsl", "uc", "hu", "iprog", "ka1", "ka3",
-                                                    "kb2", "kc1", "kc3", "mc5p", "rmp", "acsc",
-                                                    "pln", "kcbt", "smxon", "rmxon", "smam",
-                                                    "rmam", "xonc", "xoffc", "enacs", "smln",
-                                                    "rmln", "kbeg", "kcan", "kclo", "kcmd",
-                                                    "kcpy", "kcrt", "kend", "kent", "kext",
-                                                    "kfnd", "khlp", "kmrk", "kmsg", "kmov",
-                                                    "knxt", "kopn", "kopt", "kprv", "kprt",
-                                                    "krdo", "kref", "krfr", "krpl", "krst",
-                                                    "kres", "ksav", "kspd", "kund", "kBEG",
-                                                    "kCAN", "kCMD", "kCPY", "kCRT", "kDC", "kDL",
-                                                    "kslt", "kEND", "kEOL", "kEXT", "kFND",
-                                                    "kHLP", "kHOM", "kIC", "kLFT", "kMSG", "kMOV",
-                                                    "kNXT", "kOPT", "kPRV", "kPRT", "kRDO",
-                                                    "kRPL", "kRIT", "kRES", "kSAV", "kSPD",
-                                                    "kUND", "rfi", "kf11", "kf12", "kf13", "kf14",
-                                                    "kf15", "kf16", "kf17", "kf18", "kf19",
-                                                    "kf20", "kf21", "kf22", "kf23", "kf24",
-                                                    "kf25", "kf26", "kf27", "kf28", "kf29",
-                                                    "kf30", "kf31", "kf32", "kf33", "kf34",
-                                                    "kf35", "kf36", "kf37", "kf38", "kf39",
-                                                    "kf40", "kf41", "kf42", "kf43", "kf44",
-                                                    "kf45", "kf46", "kf47", "kf48", "kf49",
-                                                    "kf50", "kf51", "kf52", "kf53", "kf54",
-                                                    "kf55", "kf56", "kf57", "kf58", "kf59",
-                                                    "kf60", "kf61", "kf62", "kf63", "el1", "mgc",
-                                                    "smgl", "smgr", "fln", "sclk", "dclk",
-                                                    "rmclk", "cwin", "wingo", "hup", "dial",
-                                                    "qdial", "tone", "pulse", "hook", "pause",
-                                                    "wait", "u0", "u1", "u2", "u3", "u4", "u5",
-                                                    "u6", "u7", "u8", "u9", "op", "oc", "initc",
-                                                    "initp", "scp", "setf", "setb", "cpi", "lpi",
-                                                    "chr", "cvr", "defc", "swidm", "sdrfq",
-                                                    "sitm", "slm", "smicm", "snlq", "snrmq",
-                                                    "sshm", "ssubm", "ssupm", "sum", "rwidm",
-                                                    "ritm", "rlm", "rmicm", "rshm", "rsubm",
-                                                    "rsupm", "rum", "mhpa", "mcud1", "mcub1",
-                                                    "mcuf1", "mvpa", "mcuu1", "porder", "mcud",
-                                                    "mcub", "mcuf", "mcuu", "scs", "smgb",
-                                                    "smgbp", "smglp", "smgrp", "smgt", "smgtp",
-                                                    "sbim", "scsd", "rbim", "rcsd", "subcs",
-                                                    "supcs", "docr", "zerom", "csnm", "kmous",
-                                                    "minfo", "reqmp", "getm", "setaf", "setab",
-                                                    "pfxl", "devt", "csin", "s0ds", "s1ds",
-                                                    "s2ds", "s3ds", "smglr", "smgtb", "birep",
-                                                    "binel", "bicr", "colornm", "defbi", "endbi",
-                                                    "setcolor", "slines", "dispc", "smpch",
-                                                    "rmpch", "smsc", "rmsc", "pctrm", "scesc",
-                                                    "scesa", "ehhlm", "elhlm", "elohlm", "erhlm",
-                                                    "ethlm", "evhlm", "sgr1", "slength", "OTi2",
-                                                    "OTrs", "OTnl", "OTbs", "OTko", "OTma",
-                                                    "OTG2", "OTG3", "OTG1", "OTG4", "OTGR",
-                                                    "OTGL", "OTGU", "OTGD", "OTGH", "OTGV",
-                                                    "OTGC", "meml", "memu", "box1"];
+//! The standard capabilities, in the order ncurses uses in its compiled format.
+//!
+//! Each table below generates the name array (e.g. `boolnames`), the arrays of termcap codes
+//! (e.g. `boolcodes`) and long ("variable") names (e.g. `boolfnames`), and the matching typed key
+//! enum (e.g. `BoolCap`). Variants are named after the long names. Each capability also has a
+//! category and a description, taken from terminfo(5) where it has one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// What a standard capability is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    /// Properties and glitches of the terminal as a whole, like its size.
+    Terminal,
+    /// Cursor motion and visibility.
+    Cursor,
+    /// Scrolling and scrolling regions.
+    Scrolling,
+    /// Clearing, inserting and deleting text.
+    Editing,
+    /// Video attributes like bold and standout.
+    Attributes,
+    /// Color support and color setting.
+    Colors,
+    /// Strings sent by keys and keypad modes.
+    Keys,
+    /// Soft labels for function keys.
+    Labels,
+    /// The status line.
+    StatusLine,
+    /// Tab stops.
+    Tabs,
+    /// Margins, for terminals and printers that set them.
+    Margins,
+    /// Alternate character sets and code sets.
+    Charset,
+    /// Printers and hardcopy terminals.
+    Printer,
+    /// Mouse and other pointer input.
+    Mouse,
+    /// Initialization and reset.
+    Init,
+    /// Padding and flow control.
+    Padding,
+    /// Modem dialing.
+    Dialing,
+    /// Anything not in another category.
+    Other,
+}
+
+impl Category {
+    /// Every category, in order.
+    pub const ALL: &'static [Category] = &[Category::Terminal,
+                                           Category::Cursor,
+                                           Category::Scrolling,
+                                           Category::Editing,
+                                           Category::Attributes,
+                                           Category::Colors,
+                                           Category::Keys,
+                                           Category::Labels,
+                                           Category::StatusLine,
+                                           Category::Tabs,
+                                           Category::Margins,
+                                           Category::Charset,
+                                           Category::Printer,
+                                           Category::Mouse,
+                                           Category::Init,
+                                           Category::Padding,
+                                           Category::Dialing,
+                                           Category::Other];
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Category::Terminal => "terminal properties",
+            Category::Cursor => "cursor motion",
+            Category::Scrolling => "scrolling",
+            Category::Editing => "clearing, insertion and deletion",
+            Category::Attributes => "video attributes",
+            Category::Colors => "colors",
+            Category::Keys => "keys",
+            Category::Labels => "soft labels",
+            Category::StatusLine => "status line",
+            Category::Tabs => "tabs",
+            Category::Margins => "margins",
+            Category::Charset => "character sets",
+            Category::Printer => "printer",
+            Category::Mouse => "mouse",
+            Category::Init => "initialization and reset",
+            Category::Padding => "padding and flow control",
+            Category::Dialing => "dialing",
+            Category::Other => "other",
+        })
+    }
+}
+
+macro_rules! capabilities {
+    ($(#[$attr:meta])* pub enum $Cap:ident, $names:ident, $codes:ident, $fnames:ident {
+        $($Variant:ident = $name:expr, $code:expr, $fname:expr, $category:ident, $desc:expr,)*
+    }) => {
+        /// The terminfo names of the capabilities, in compiled format order.
+        pub static $names: &[&str] = &[$($name),*];
+        /// The termcap codes of the capabilities, in compiled format order.
+        pub static $codes: &[&str] = &[$($code),*];
+        /// The long names of the capabilities, in compiled format order.
+        pub static $fnames: &[&str] = &[$($fname),*];
+
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum $Cap {
+            $(
+                #[doc = concat!("`", $name, "`: ", $desc)]
+                $Variant,
+            )*
+        }
+
+        impl $Cap {
+            /// Every capability of this type, in compiled format order.
+            pub const ALL: &'static [$Cap] = &[$($Cap::$Variant),*];
+
+            const CATEGORIES: &'static [Category] = &[$(Category::$category),*];
+            const DESCRIPTIONS: &'static [&'static str] = &[$($desc),*];
+
+            /// The terminfo (short) name of the capability.
+            pub fn name(self) -> &'static str {
+                $names[self as usize]
+            }
+
+            /// The index of the capability in the compiled format.
+            pub fn index(self) -> usize {
+                self as usize
+            }
+
+            /// The termcap (two-letter) code of the capability.
+            pub fn termcap(self) -> &'static str {
+                $codes[self as usize]
+            }
+
+            /// The long name of the capability, like `auto_right_margin`.
+            pub fn long_name(self) -> &'static str {
+                $fnames[self as usize]
+            }
+
+            /// What the capability is about.
+            pub fn category(self) -> Category {
+                $Cap::CATEGORIES[self as usize]
+            }
+
+            /// A short description of the capability, like `move to row #1 columns #2`.
+            pub fn description(self) -> &'static str {
+                $Cap::DESCRIPTIONS[self as usize]
+            }
+
+            /// Look up a standard capability by its terminfo name.
+            pub fn from_name(name: &str) -> Option<$Cap> {
+                static INDEX: OnceLock<HashMap<&'static str, $Cap>> = OnceLock::new();
+                INDEX.get_or_init(|| $Cap::ALL.iter().map(|&cap| (cap.name(), cap)).collect())
+                     .get(name)
+                     .cloned()
+            }
+
+            /// Look up a standard capability by its termcap code.
+            ///
+            /// `ML` is shared by `smgl` and `smglr`; like `tic`, it stands for `smglr`.
+            pub fn from_termcap(code: &str) -> Option<$Cap> {
+                static INDEX: OnceLock<HashMap<&'static str, $Cap>> = OnceLock::new();
+                INDEX.get_or_init(|| $Cap::ALL.iter().map(|&cap| (cap.termcap(), cap)).collect())
+                     .get(code)
+                     .cloned()
+            }
+
+            /// Look up a standard capability by its long name.
+            pub fn from_long_name(name: &str) -> Option<$Cap> {
+                static INDEX: OnceLock<HashMap<&'static str, $Cap>> = OnceLock::new();
+                INDEX.get_or_init(|| $Cap::ALL.iter().map(|&cap| (cap.long_name(), cap)).collect())
+                     .get(name)
+                     .cloned()
+            }
+        }
+
+        impl ::std::fmt::Display for $Cap {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    }
+}
+
+/// Translate a name between namespaces, looking through booleans, numbers and strings in turn.
+macro_rules! translate {
+    ($name:expr, $from:ident, $to:ident) => {
+        BoolCap::$from($name).map(BoolCap::$to)
+                             .or_else(|| NumberCap::$from($name).map(NumberCap::$to))
+                             .or_else(|| StringCap::$from($name).map(StringCap::$to))
+    }
+}
+
+/// The termcap code of the standard capability with the given terminfo name.
+pub fn termcap_from_terminfo(name: &str) -> Option<&'static str> {
+    translate!(name, from_name, termcap)
+}
+
+/// The long name of the standard capability with the given terminfo name.
+pub fn long_name_from_terminfo(name: &str) -> Option<&'static str> {
+    translate!(name, from_name, long_name)
+}
+
+/// The terminfo name of the standard capability with the given termcap code.
+///
+/// `MT` is both the boolean `OTMT` and the string `smgtb`; it gives the boolean.
+pub fn terminfo_from_termcap(code: &str) -> Option<&'static str> {
+    translate!(code, from_termcap, name)
+}
+
+/// The long name of the standard capability with the given termcap code, like
+/// `terminfo_from_termcap`.
+pub fn long_name_from_termcap(code: &str) -> Option<&'static str> {
+    translate!(code, from_termcap, long_name)
+}
+
+/// The terminfo name of the standard capability with the given long name.
+pub fn terminfo_from_long_name(name: &str) -> Option<&'static str> {
+    translate!(name, from_long_name, name)
+}
+
+/// The termcap code of the standard capability with the given long name.
+pub fn termcap_from_long_name(name: &str) -> Option<&'static str> {
+    translate!(name, from_long_name, termcap)
+}
+
+/// The category of the standard capability with the given terminfo name.
+pub fn category(name: &str) -> Option<Category> {
+    translate!(name, from_name, category)
+}
+
+capabilities! {
+    /// A standard boolean capability.
+    pub enum BoolCap, boolnames, boolcodes, boolfnames {
+        AutoLeftMargin = "bw", "bw", "auto_left_margin",
+            Terminal, "cub1 wraps from column 0 to last column",
+        AutoRightMargin = "am", "am", "auto_right_margin",
+            Terminal, "terminal has automatic margins",
+        NoEscCtlc = "xsb", "xb", "no_esc_ctlc",
+            Terminal, "beehive (f1=escape, f2=ctrl C)",
+        CeolStandoutGlitch = "xhp", "xs", "ceol_standout_glitch",
+            Terminal, "standout not erased by overwriting (hp)",
+        EatNewlineGlitch = "xenl", "xn", "eat_newline_glitch",
+            Terminal, "newline ignored after 80 cols (concept)",
+        EraseOverstrike = "eo", "eo", "erase_overstrike",
+            Terminal, "can erase overstrikes with a blank",
+        GenericType = "gn", "gn", "generic_type",
+            Terminal, "generic line type",
+        HardCopy = "hc", "hc", "hard_copy",
+            Terminal, "hardcopy terminal",
+        HasMetaKey = "km", "km", "has_meta_key",
+            Terminal, "has a meta key (i.e., sets 8th-bit)",
+        HasStatusLine = "hs", "hs", "has_status_line",
+            Terminal, "has extra status line",
+        InsertNullGlitch = "in", "in", "insert_null_glitch",
+            Terminal, "insert mode distinguishes nulls",
+        MemoryAbove = "da", "da", "memory_above",
+            Terminal, "display may be retained above the screen",
+        MemoryBelow = "db", "db", "memory_below",
+            Terminal, "display may be retained below the screen",
+        MoveInsertMode = "mir", "mi", "move_insert_mode",
+            Cursor, "safe to move while in insert mode",
+        MoveStandoutMode = "msgr", "ms", "move_standout_mode",
+            Attributes, "safe to move while in standout mode",
+        OverStrike = "os", "os", "over_strike",
+            Terminal, "terminal can overstrike",
+        StatusLineEscOk = "eslok", "es", "status_line_esc_ok",
+            StatusLine, "escape can be used on the status line",
+        DestTabsMagicSmso = "xt", "xt", "dest_tabs_magic_smso",
+            Terminal, "tabs destructive, magic so char (t1061)",
+        TildeGlitch = "hz", "hz", "tilde_glitch",
+            Terminal, "cannot print ~'s (Hazeltine)",
+        TransparentUnderline = "ul", "ul", "transparent_underline",
+            Terminal, "underline character overstrikes",
+        XonXoff = "xon", "xo", "xon_xoff",
+            Padding, "terminal uses xon/xoff handshaking",
+        NeedsXonXoff = "nxon", "nx", "needs_xon_xoff",
+            Padding, "padding will not work, xon/xoff required",
+        PrtrSilent = "mc5i", "5i", "prtr_silent",
+            Printer, "printer will not echo on screen",
+        HardCursor = "chts", "HC", "hard_cursor",
+            Terminal, "cursor is hard to see",
+        NonRevRmcup = "nrrmc", "NR", "non_rev_rmcup",
+            Terminal, "smcup does not reverse rmcup",
+        NoPadChar = "npc", "NP", "no_pad_char",
+            Padding, "pad character does not exist",
+        NonDestScrollRegion = "ndscr", "ND", "non_dest_scroll_region",
+            Terminal, "scrolling region is non-destructive",
+        CanChange = "ccc", "cc", "can_change",
+            Colors, "terminal can re-define existing colors",
+        BackColorErase = "bce", "ut", "back_color_erase",
+            Colors, "screen erased with background color",
+        HueLightnessSaturation = "hls", "hl", "hue_lightness_saturation",
+            Colors, "terminal uses only HLS color notation (Tektronix)",
+        ColAddrGlitch = "xhpa", "YA", "col_addr_glitch",
+            Terminal, "only positive motion for hpa/mhpa caps",
+        CrCancelsMicroMode = "crxm", "YB", "cr_cancels_micro_mode",
+            Printer, "using cr turns off micro mode",
+        HasPrintWheel = "daisy", "YC", "has_print_wheel",
+            Printer, "printer needs operator to change character set",
+        RowAddrGlitch = "xvpa", "YD", "row_addr_glitch",
+            Terminal, "only positive motion for vpa/mvpa caps",
+        SemiAutoRightMargin = "sam", "YE", "semi_auto_right_margin",
+            Terminal, "printing in last column causes cr",
+        CpiChangesRes = "cpix", "YF", "cpi_changes_res",
+            Printer, "changing character pitch changes resolution",
+        LpiChangesRes = "lpix", "YG", "lpi_changes_res",
+            Printer, "changing line pitch changes resolution",
+        BackspacesWithBs = "OTbs", "bs", "backspaces_with_bs",
+            Terminal, "uses ^H to move left",
+        CrtNoScrolling = "OTns", "ns", "crt_no_scrolling",
+            Terminal, "crt cannot scroll",
+        NoCorrectlyWorkingCr = "OTnc", "nc", "no_correctly_working_cr",
+            Terminal, "no way to go to start of line",
+        GnuHasMetaKey = "OTMT", "MT", "gnu_has_meta_key",
+            Terminal, "has meta key",
+        LinefeedIsNewline = "OTNL", "NL", "linefeed_is_newline",
+            Terminal, "move down with \\n",
+        HasHardwareTabs = "OTpt", "pt", "has_hardware_tabs",
+            Tabs, "has 8-char tabs invoked with ^I",
+        ReturnDoesClrEol = "OTxr", "xr", "return_does_clr_eol",
+            Terminal, "return clears the line",
+    }
+}
+
+capabilities! {
+    /// A standard numeric capability.
+    pub enum NumberCap, numnames, numcodes, numfnames {
+        Columns = "cols", "co", "columns",
+            Terminal, "number of columns in a line",
+        InitTabs = "it", "it", "init_tabs",
+            Tabs, "tabs initially every # spaces",
+        Lines = "lines", "li", "lines",
+            Terminal, "number of lines on screen or page",
+        LinesOfMemory = "lm", "lm", "lines_of_memory",
+            Terminal, "lines of memory if > line. 0 means varies",
+        MagicCookieGlitch = "xmc", "sg", "magic_cookie_glitch",
+            Attributes, "number of blank characters left by smso or rmso",
+        PaddingBaudRate = "pb", "pb", "padding_baud_rate",
+            Padding, "lowest baud rate where padding needed",
+        VirtualTerminal = "vt", "vt", "virtual_terminal",
+            Terminal, "virtual terminal number (CB/unix)",
+        WidthStatusLine = "wsl", "ws", "width_status_line",
+            StatusLine, "number of columns in status line",
+        NumLabels = "nlab", "Nl", "num_labels",
+            Labels, "number of labels on screen",
+        LabelHeight = "lh", "lh", "label_height",
+            Labels, "rows in each label",
+        LabelWidth = "lw", "lw", "label_width",
+            Labels, "columns in each label",
+        MaxAttributes = "ma", "ma", "max_attributes",
+            Attributes, "maximum combined attributes terminal can handle",
+        MaximumWindows = "wnum", "MW", "maximum_windows",
+            Other, "maximum number of definable windows",
+        MaxColors = "colors", "Co", "max_colors",
+            Colors, "maximum number of colors on screen",
+        MaxPairs = "pairs", "pa", "max_pairs",
+            Colors, "maximum number of color-pairs on the screen",
+        NoColorVideo = "ncv", "NC", "no_color_video",
+            Attributes, "video attributes that cannot be used with colors",
+        BufferCapacity = "bufsz", "Ya", "buffer_capacity",
+            Printer, "numbers of bytes buffered before printing",
+        DotVertSpacing = "spinv", "Yb", "dot_vert_spacing",
+            Printer, "spacing of pins vertically in pins per inch",
+        DotHorzSpacing = "spinh", "Yc", "dot_horz_spacing",
+            Printer, "spacing of dots horizontally in dots per inch",
+        MaxMicroAddress = "maddr", "Yd", "max_micro_address",
+            Printer, "maximum value in micro_..._address",
+        MaxMicroJump = "mjump", "Ye", "max_micro_jump",
+            Printer, "maximum value in parm_..._micro",
+        MicroColSize = "mcs", "Yf", "micro_col_size",
+            Printer, "character step size when in micro mode",
+        MicroLineSize = "mls", "Yg", "micro_line_size",
+            Printer, "line step size when in micro mode",
+        NumberOfPins = "npins", "Yh", "number_of_pins",
+            Printer, "numbers of pins in print-head",
+        OutputResChar = "orc", "Yi", "output_res_char",
+            Printer, "horizontal resolution in units per line",
+        OutputResLine = "orl", "Yj", "output_res_line",
+            Printer, "vertical resolution in units per line",
+        OutputResHorzInch = "orhi", "Yk", "output_res_horz_inch",
+            Printer, "horizontal resolution in units per inch",
+        OutputResVertInch = "orvi", "Yl", "output_res_vert_inch",
+            Printer, "vertical resolution in units per inch",
+        PrintRate = "cps", "Ym", "print_rate",
+            Printer, "print rate in characters per second",
+        WideCharSize = "widcs", "Yn", "wide_char_size",
+            Printer, "character step size when in double wide mode",
+        Buttons = "btns", "BT", "buttons",
+            Mouse, "number of buttons on mouse",
+        BitImageEntwining = "bitwin", "Yo", "bit_image_entwining",
+            Printer, "number of passes for each bit-image row",
+        BitImageType = "bitype", "Yp", "bit_image_type",
+            Printer, "type of bit-image device",
+        MagicCookieGlitchUl = "OTug", "ug", "magic_cookie_glitch_ul",
+            Attributes, "number of blanks left by ul",
+        CarriageReturnDelay = "OTdC", "dC", "carriage_return_delay",
+            Padding, "pad needed for CR",
+        NewLineDelay = "OTdN", "dN", "new_line_delay",
+            Padding, "pad needed for LF",
+        BackspaceDelay = "OTdB", "dB", "backspace_delay",
+            Padding, "padding required for ^H",
+        HorizontalTabDelay = "OTdT", "dT", "horizontal_tab_delay",
+            Tabs, "padding required for ^I",
+        NumberOfFunctionKeys = "OTkn", "kn", "number_of_function_keys",
+            Keys, "count of function keys",
+    }
+}
+
+capabilities! {
+    /// A standard string capability.
+    pub enum StringCap, stringnames, stringcodes, stringfnames {
+        BackTab = "cbt", "bt", "back_tab",
+            Tabs, "back tab",
+        Bell = "bel", "bl", "bell",
+            Other, "audible signal (bell)",
+        CarriageReturn = "cr", "cr", "carriage_return",
+            Cursor, "carriage return",
+        ChangeScrollRegion = "csr", "cs", "change_scroll_region",
+            Scrolling, "change region to line #1 to line #2",
+        ClearAllTabs = "tbc", "ct", "clear_all_tabs",
+            Tabs, "clear all tab stops",
+        ClearScreen = "clear", "cl", "clear_screen",
+            Editing, "clear screen and home cursor",
+        ClrEol = "el", "ce", "clr_eol",
+            Editing, "clear to end of line",
+        ClrEos = "ed", "cd", "clr_eos",
+            Editing, "clear to end of screen",
+        ColumnAddress = "hpa", "ch", "column_address",
+            Cursor, "horizontal position #1, absolute",
+        CommandCharacter = "cmdch", "CC", "command_character",
+            Other, "terminal settable cmd character in prototype !?",
+        CursorAddress = "cup", "cm", "cursor_address",
+            Cursor, "move to row #1 columns #2",
+        CursorDown = "cud1", "do", "cursor_down",
+            Cursor, "down one line",
+        CursorHome = "home", "ho", "cursor_home",
+            Cursor, "home cursor (if no cup)",
+        CursorInvisible = "civis", "vi", "cursor_invisible",
+            Cursor, "make cursor invisible",
+        CursorLeft = "cub1", "le", "cursor_left",
+            Cursor, "move left one space",
+        CursorMemAddress = "mrcup", "CM", "cursor_mem_address",
+            Cursor, "memory relative cursor addressing, move to row #1 columns #2",
+        CursorNormal = "cnorm", "ve", "cursor_normal",
+            Cursor, "make cursor appear normal (undo civis/cvvis)",
+        CursorRight = "cuf1", "nd", "cursor_right",
+            Cursor, "non-destructive space (move right one space)",
+        CursorToLl = "ll", "ll", "cursor_to_ll",
+            Cursor, "last line, first column (if no cup)",
+        CursorUp = "cuu1", "up", "cursor_up",
+            Cursor, "up one line",
+        CursorVisible = "cvvis", "vs", "cursor_visible",
+            Cursor, "make cursor very visible",
+        DeleteCharacter = "dch1", "dc", "delete_character",
+            Editing, "delete character",
+        DeleteLine = "dl1", "dl", "delete_line",
+            Editing, "delete line",
+        DisStatusLine = "dsl", "ds", "dis_status_line",
+            StatusLine, "disable status line",
+        DownHalfLine = "hd", "hd", "down_half_line",
+            Cursor, "half a line down",
+        EnterAltCharsetMode = "smacs", "as", "enter_alt_charset_mode",
+            Attributes, "start alternate character set",
+        EnterBlinkMode = "blink", "mb", "enter_blink_mode",
+            Attributes, "turn on blinking",
+        EnterBoldMode = "bold", "md", "enter_bold_mode",
+            Attributes, "turn on bold (extra bright) mode",
+        EnterCaMode = "smcup", "ti", "enter_ca_mode",
+            Init, "string to start programs using cup",
+        EnterDeleteMode = "smdc", "dm", "enter_delete_mode",
+            Editing, "enter delete mode",
+        EnterDimMode = "dim", "mh", "enter_dim_mode",
+            Attributes, "turn on half-bright mode",
+        EnterInsertMode = "smir", "im", "enter_insert_mode",
+            Editing, "enter insert mode",
+        EnterSecureMode = "invis", "mk", "enter_secure_mode",
+            Attributes, "turn on blank mode (characters invisible)",
+        EnterProtectedMode = "prot", "mp", "enter_protected_mode",
+            Attributes, "turn on protected mode",
+        EnterReverseMode = "rev", "mr", "enter_reverse_mode",
+            Attributes, "turn on reverse video mode",
+        EnterStandoutMode = "smso", "so", "enter_standout_mode",
+            Attributes, "begin standout mode",
+        EnterUnderlineMode = "smul", "us", "enter_underline_mode",
+            Attributes, "begin underline mode",
+        EraseChars = "ech", "ec", "erase_chars",
+            Editing, "erase #1 characters",
+        ExitAltCharsetMode = "rmacs", "ae", "exit_alt_charset_mode",
+            Attributes, "end alternate character set",
+        ExitAttributeMode = "sgr0", "me", "exit_attribute_mode",
+            Attributes, "turn off all attributes",
+        ExitCaMode = "rmcup", "te", "exit_ca_mode",
+            Init, "strings to end programs using cup",
+        ExitDeleteMode = "rmdc", "ed", "exit_delete_mode",
+            Editing, "end delete mode",
+        ExitInsertMode = "rmir", "ei", "exit_insert_mode",
+            Editing, "exit insert mode",
+        ExitStandoutMode = "rmso", "se", "exit_standout_mode",
+            Attributes, "exit standout mode",
+        ExitUnderlineMode = "rmul", "ue", "exit_underline_mode",
+            Attributes, "exit underline mode",
+        FlashScreen = "flash", "vb", "flash_screen",
+            Other, "visible bell (may not move cursor)",
+        FormFeed = "ff", "ff", "form_feed",
+            Printer, "hardcopy terminal page eject",
+        FromStatusLine = "fsl", "fs", "from_status_line",
+            StatusLine, "return from status line",
+        Init1string = "is1", "i1", "init_1string",
+            Init, "initialization string",
+        Init2string = "is2", "is", "init_2string",
+            Init, "initialization string",
+        Init3string = "is3", "i3", "init_3string",
+            Init, "initialization string",
+        InitFile = "if", "if", "init_file",
+            Init, "name of initialization file",
+        InsertCharacter = "ich1", "ic", "insert_character",
+            Editing, "insert character",
+        InsertLine = "il1", "al", "insert_line",
+            Editing, "insert line",
+        InsertPadding = "ip", "ip", "insert_padding",
+            Editing, "insert padding after inserted character",
+        KeyBackspace = "kbs", "kb", "key_backspace",
+            Keys, "backspace key",
+        KeyCatab = "ktbc", "ka", "key_catab",
+            Keys, "clear-all-tabs key",
+        KeyClear = "kclr", "kC", "key_clear",
+            Keys, "clear-screen or erase key",
+        KeyCtab = "kctab", "kt", "key_ctab",
+            Keys, "clear-tab key",
+        KeyDc = "kdch1", "kD", "key_dc",
+            Keys, "delete-character key",
+        KeyDl = "kdl1", "kL", "key_dl",
+            Keys, "delete-line key",
+        KeyDown = "kcud1", "kd", "key_down",
+            Keys, "down-arrow key",
+        KeyEic = "krmir", "kM", "key_eic",
+            Keys, "sent by rmir or smir in insert mode",
+        KeyEol = "kel", "kE", "key_eol",
+            Keys, "clear-to-end-of-line key",
+        KeyEos = "ked", "kS", "key_eos",
+            Keys, "clear-to-end-of-screen key",
+        KeyF0 = "kf0", "k0", "key_f0",
+            Keys, "F0 function key",
+        KeyF1 = "kf1", "k1", "key_f1",
+            Keys, "F1 function key",
+        KeyF10 = "kf10", "k;", "key_f10",
+            Keys, "F10 function key",
+        KeyF2 = "kf2", "k2", "key_f2",
+            Keys, "F2 function key",
+        KeyF3 = "kf3", "k3", "key_f3",
+            Keys, "F3 function key",
+        KeyF4 = "kf4", "k4", "key_f4",
+            Keys, "F4 function key",
+        KeyF5 = "kf5", "k5", "key_f5",
+            Keys, "F5 function key",
+        KeyF6 = "kf6", "k6", "key_f6",
+            Keys, "F6 function key",
+        KeyF7 = "kf7", "k7", "key_f7",
+            Keys, "F7 function key",
+        KeyF8 = "kf8", "k8", "key_f8",
+            Keys, "F8 function key",
+        KeyF9 = "kf9", "k9", "key_f9",
+            Keys, "F9 function key",
+        KeyHome = "khome", "kh", "key_home",
+            Keys, "home key",
+        KeyIc = "kich1", "kI", "key_ic",
+            Keys, "insert-character key",
+        KeyIl = "kil1", "kA", "key_il",
+            Keys, "insert-line key",
+        KeyLeft = "kcub1", "kl", "key_left",
+            Keys, "left-arrow key",
+        KeyLl = "kll", "kH", "key_ll",
+            Keys, "lower-left key (home down)",
+        KeyNpage = "knp", "kN", "key_npage",
+            Keys, "next-page key",
+        KeyPpage = "kpp", "kP", "key_ppage",
+            Keys, "previous-page key",
+        KeyRight = "kcuf1", "kr", "key_right",
+            Keys, "right-arrow key",
+        KeySf = "kind", "kF", "key_sf",
+            Keys, "scroll-forward key",
+        KeySr = "kri", "kR", "key_sr",
+            Keys, "scroll-backward key",
+        KeyStab = "khts", "kT", "key_stab",
+            Keys, "set-tab key",
+        KeyUp = "kcuu1", "ku", "key_up",
+            Keys, "up-arrow key",
+        KeypadLocal = "rmkx", "ke", "keypad_local",
+            Keys, "leave 'keyboard_transmit' mode",
+        KeypadXmit = "smkx", "ks", "keypad_xmit",
+            Keys, "enter 'keyboard_transmit' mode",
+        LabF0 = "lf0", "l0", "lab_f0",
+            Labels, "label on function key f0 if not f0",
+        LabF1 = "lf1", "l1", "lab_f1",
+            Labels, "label on function key f1 if not f1",
+        LabF10 = "lf10", "la", "lab_f10",
+            Labels, "label on function key f10 if not f10",
+        LabF2 = "lf2", "l2", "lab_f2",
+            Labels, "label on function key f2 if not f2",
+        LabF3 = "lf3", "l3", "lab_f3",
+            Labels, "label on function key f3 if not f3",
+        LabF4 = "lf4", "l4", "lab_f4",
+            Labels, "label on function key f4 if not f4",
+        LabF5 = "lf5", "l5", "lab_f5",
+            Labels, "label on function key f5 if not f5",
+        LabF6 = "lf6", "l6", "lab_f6",
+            Labels, "label on function key f6 if not f6",
+        LabF7 = "lf7", "l7", "lab_f7",
+            Labels, "label on function key f7 if not f7",
+        LabF8 = "lf8", "l8", "lab_f8",
+            Labels, "label on function key f8 if not f8",
+        LabF9 = "lf9", "l9", "lab_f9",
+            Labels, "label on function key f9 if not f9",
+        MetaOff = "rmm", "mo", "meta_off",
+            Charset, "turn off meta mode",
+        MetaOn = "smm", "mm", "meta_on",
+            Charset, "turn on meta mode (8th-bit on)",
+        Newline = "nel", "nw", "newline",
+            Cursor, "newline (behave like cr followed by lf)",
+        PadChar = "pad", "pc", "pad_char",
+            Padding, "padding char (instead of null)",
+        ParmDch = "dch", "DC", "parm_dch",
+            Editing, "delete #1 characters",
+        ParmDeleteLine = "dl", "DL", "parm_delete_line",
+            Editing, "delete #1 lines",
+        ParmDownCursor = "cud", "DO", "parm_down_cursor",
+            Cursor, "down #1 lines",
+        ParmIch = "ich", "IC", "parm_ich",
+            Editing, "insert #1 characters",
+        ParmIndex = "indn", "SF", "parm_index",
+            Scrolling, "scroll forward #1 lines",
+        ParmInsertLine = "il", "AL", "parm_insert_line",
+            Editing, "insert #1 lines",
+        ParmLeftCursor = "cub", "LE", "parm_left_cursor",
+            Cursor, "move #1 characters to the left",
+        ParmRightCursor = "cuf", "RI", "parm_right_cursor",
+            Cursor, "move #1 characters to the right",
+        ParmRindex = "rin", "SR", "parm_rindex",
+            Scrolling, "scroll back #1 lines",
+        ParmUpCursor = "cuu", "UP", "parm_up_cursor",
+            Cursor, "up #1 lines",
+        PkeyKey = "pfkey", "pk", "pkey_key",
+            Keys, "program function key #1 to type string #2",
+        PkeyLocal = "pfloc", "pl", "pkey_local",
+            Keys, "program function key #1 to execute string #2",
+        PkeyXmit = "pfx", "px", "pkey_xmit",
+            Keys, "program function key #1 to transmit string #2",
+        PrintScreen = "mc0", "ps", "print_screen",
+            Printer, "print contents of screen",
+        PrtrOff = "mc4", "pf", "prtr_off",
+            Printer, "turn off printer",
+        PrtrOn = "mc5", "po", "prtr_on",
+            Printer, "turn on printer",
+        RepeatChar = "rep", "rp", "repeat_char",
+            Editing, "repeat char #1 #2 times",
+        Reset1string = "rs1", "r1", "reset_1string",
+            Init, "reset string",
+        Reset2string = "rs2", "r2", "reset_2string",
+            Init, "reset string",
+        Reset3string = "rs3", "r3", "reset_3string",
+            Init, "reset string",
+        ResetFile = "rf", "rf", "reset_file",
+            Init, "name of reset file",
+        RestoreCursor = "rc", "rc", "restore_cursor",
+            Cursor, "restore cursor to position of last save_cursor",
+        RowAddress = "vpa", "cv", "row_address",
+            Cursor, "vertical position #1 absolute",
+        SaveCursor = "sc", "sc", "save_cursor",
+            Cursor, "save current cursor position",
+        ScrollForward = "ind", "sf", "scroll_forward",
+            Scrolling, "scroll text up",
+        ScrollReverse = "ri", "sr", "scroll_reverse",
+            Scrolling, "scroll text down",
+        SetAttributes = "sgr", "sa", "set_attributes",
+            Attributes, "define video attributes #1-#9",
+        SetTab = "hts", "st", "set_tab",
+            Tabs, "set a tab in every row, current columns",
+        SetWindow = "wind", "wi", "set_window",
+            Scrolling, "current window is lines #1-#2 cols #3-#4",
+        Tab = "ht", "ta", "tab",
+            Tabs, "tab to next 8-space hardware tab stop",
+        ToStatusLine = "tsl", "ts", "to_status_line",
+            StatusLine, "move to status line, column #1",
+        UnderlineChar = "uc", "uc", "underline_char",
+            Attributes, "underline char and move past it",
+        UpHalfLine = "hu", "hu", "up_half_line",
+            Cursor, "half a line up",
+        InitProg = "iprog", "iP", "init_prog",
+            Init, "path name of program for initialization",
+        KeyA1 = "ka1", "K1", "key_a1",
+            Keys, "upper left of keypad",
+        KeyA3 = "ka3", "K3", "key_a3",
+            Keys, "upper right of keypad",
+        KeyB2 = "kb2", "K2", "key_b2",
+            Keys, "center of keypad",
+        KeyC1 = "kc1", "K4", "key_c1",
+            Keys, "lower left of keypad",
+        KeyC3 = "kc3", "K5", "key_c3",
+            Keys, "lower right of keypad",
+        PrtrNon = "mc5p", "pO", "prtr_non",
+            Printer, "turn on printer for #1 bytes",
+        CharPadding = "rmp", "rP", "char_padding",
+            Editing, "like ip but when in insert mode",
+        AcsChars = "acsc", "ac", "acs_chars",
+            Charset, "graphics charset pairs, based on vt100",
+        PlabNorm = "pln", "pn", "plab_norm",
+            Labels, "program label #1 to show string #2",
+        KeyBtab = "kcbt", "kB", "key_btab",
+            Keys, "back-tab key",
+        EnterXonMode = "smxon", "SX", "enter_xon_mode",
+            Padding, "turn on xon/xoff handshaking",
+        ExitXonMode = "rmxon", "RX", "exit_xon_mode",
+            Padding, "turn off xon/xoff handshaking",
+        EnterAmMode = "smam", "SA", "enter_am_mode",
+            Margins, "turn on automatic margins",
+        ExitAmMode = "rmam", "RA", "exit_am_mode",
+            Margins, "turn off automatic margins",
+        XonCharacter = "xonc", "XN", "xon_character",
+            Padding, "XON character",
+        XoffCharacter = "xoffc", "XF", "xoff_character",
+            Padding, "XOFF character",
+        EnaAcs = "enacs", "eA", "ena_acs",
+            Charset, "enable alternate char set",
+        LabelOn = "smln", "LO", "label_on",
+            Labels, "turn on soft labels",
+        LabelOff = "rmln", "LF", "label_off",
+            Labels, "turn off soft labels",
+        KeyBeg = "kbeg", "@1", "key_beg",
+            Keys, "begin key",
+        KeyCancel = "kcan", "@2", "key_cancel",
+            Keys, "cancel key",
+        KeyClose = "kclo", "@3", "key_close",
+            Keys, "close key",
+        KeyCommand = "kcmd", "@4", "key_command",
+            Keys, "command key",
+        KeyCopy = "kcpy", "@5", "key_copy",
+            Keys, "copy key",
+        KeyCreate = "kcrt", "@6", "key_create",
+            Keys, "create key",
+        KeyEnd = "kend", "@7", "key_end",
+            Keys, "end key",
+        KeyEnter = "kent", "@8", "key_enter",
+            Keys, "enter/send key",
+        KeyExit = "kext", "@9", "key_exit",
+            Keys, "exit key",
+        KeyFind = "kfnd", "@0", "key_find",
+            Keys, "find key",
+        KeyHelp = "khlp", "%1", "key_help",
+            Keys, "help key",
+        KeyMark = "kmrk", "%2", "key_mark",
+            Keys, "mark key",
+        KeyMessage = "kmsg", "%3", "key_message",
+            Keys, "message key",
+        KeyMove = "kmov", "%4", "key_move",
+            Keys, "move key",
+        KeyNext = "knxt", "%5", "key_next",
+            Keys, "next key",
+        KeyOpen = "kopn", "%6", "key_open",
+            Keys, "open key",
+        KeyOptions = "kopt", "%7", "key_options",
+            Keys, "options key",
+        KeyPrevious = "kprv", "%8", "key_previous",
+            Keys, "previous key",
+        KeyPrint = "kprt", "%9", "key_print",
+            Keys, "print key",
+        KeyRedo = "krdo", "%0", "key_redo",
+            Keys, "redo key",
+        KeyReference = "kref", "&1", "key_reference",
+            Keys, "reference key",
+        KeyRefresh = "krfr", "&2", "key_refresh",
+            Keys, "refresh key",
+        KeyReplace = "krpl", "&3", "key_replace",
+            Keys, "replace key",
+        KeyRestart = "krst", "&4", "key_restart",
+            Keys, "restart key",
+        KeyResume = "kres", "&5", "key_resume",
+            Keys, "resume key",
+        KeySave = "ksav", "&6", "key_save",
+            Keys, "save key",
+        KeySuspend = "kspd", "&7", "key_suspend",
+            Keys, "suspend key",
+        KeyUndo = "kund", "&8", "key_undo",
+            Keys, "undo key",
+        KeySbeg = "kBEG", "&9", "key_sbeg",
+            Keys, "shifted begin key",
+        KeyScancel = "kCAN", "&0", "key_scancel",
+            Keys, "shifted cancel key",
+        KeyScommand = "kCMD", "*1", "key_scommand",
+            Keys, "shifted command key",
+        KeyScopy = "kCPY", "*2", "key_scopy",
+            Keys, "shifted copy key",
+        KeyScreate = "kCRT", "*3", "key_screate",
+            Keys, "shifted create key",
+        KeySdc = "kDC", "*4", "key_sdc",
+            Keys, "shifted delete-character key",
+        KeySdl = "kDL", "*5", "key_sdl",
+            Keys, "shifted delete-line key",
+        KeySelect = "kslt", "*6", "key_select",
+            Keys, "select key",
+        KeySend = "kEND", "*7", "key_send",
+            Keys, "shifted end key",
+        KeySeol = "kEOL", "*8", "key_seol",
+            Keys, "shifted clear-to-end-of-line key",
+        KeySexit = "kEXT", "*9", "key_sexit",
+            Keys, "shifted exit key",
+        KeySfind = "kFND", "*0", "key_sfind",
+            Keys, "shifted find key",
+        KeyShelp = "kHLP", "#1", "key_shelp",
+            Keys, "shifted help key",
+        KeyShome = "kHOM", "#2", "key_shome",
+            Keys, "shifted home key",
+        KeySic = "kIC", "#3", "key_sic",
+            Keys, "shifted insert-character key",
+        KeySleft = "kLFT", "#4", "key_sleft",
+            Keys, "shifted left-arrow key",
+        KeySmessage = "kMSG", "%a", "key_smessage",
+            Keys, "shifted message key",
+        KeySmove = "kMOV", "%b", "key_smove",
+            Keys, "shifted move key",
+        KeySnext = "kNXT", "%c", "key_snext",
+            Keys, "shifted next key",
+        KeySoptions = "kOPT", "%d", "key_soptions",
+            Keys, "shifted options key",
+        KeySprevious = "kPRV", "%e", "key_sprevious",
+            Keys, "shifted previous key",
+        KeySprint = "kPRT", "%f", "key_sprint",
+            Keys, "shifted print key",
+        KeySredo = "kRDO", "%g", "key_sredo",
+            Keys, "shifted redo key",
+        KeySreplace = "kRPL", "%h", "key_sreplace",
+            Keys, "shifted replace key",
+        KeySright = "kRIT", "%i", "key_sright",
+            Keys, "shifted right-arrow key",
+        KeySrsume = "kRES", "%j", "key_srsume",
+            Keys, "shifted resume key",
+        KeySsave = "kSAV", "!1", "key_ssave",
+            Keys, "shifted save key",
+        KeySsuspend = "kSPD", "!2", "key_ssuspend",
+            Keys, "shifted suspend key",
+        KeySundo = "kUND", "!3", "key_sundo",
+            Keys, "shifted undo key",
+        ReqForInput = "rfi", "RF", "req_for_input",
+            Other, "send next input char (for ptys)",
+        KeyF11 = "kf11", "F1", "key_f11",
+            Keys, "F11 function key",
+        KeyF12 = "kf12", "F2", "key_f12",
+            Keys, "F12 function key",
+        KeyF13 = "kf13", "F3", "key_f13",
+            Keys, "F13 function key",
+        KeyF14 = "kf14", "F4", "key_f14",
+            Keys, "F14 function key",
+        KeyF15 = "kf15", "F5", "key_f15",
+            Keys, "F15 function key",
+        KeyF16 = "kf16", "F6", "key_f16",
+            Keys, "F16 function key",
+        KeyF17 = "kf17", "F7", "key_f17",
+            Keys, "F17 function key",
+        KeyF18 = "kf18", "F8", "key_f18",
+            Keys, "F18 function key",
+        KeyF19 = "kf19", "F9", "key_f19",
+            Keys, "F19 function key",
+        KeyF20 = "kf20", "FA", "key_f20",
+            Keys, "F20 function key",
+        KeyF21 = "kf21", "FB", "key_f21",
+            Keys, "F21 function key",
+        KeyF22 = "kf22", "FC", "key_f22",
+            Keys, "F22 function key",
+        KeyF23 = "kf23", "FD", "key_f23",
+            Keys, "F23 function key",
+        KeyF24 = "kf24", "FE", "key_f24",
+            Keys, "F24 function key",
+        KeyF25 = "kf25", "FF", "key_f25",
+            Keys, "F25 function key",
+        KeyF26 = "kf26", "FG", "key_f26",
+            Keys, "F26 function key",
+        KeyF27 = "kf27", "FH", "key_f27",
+            Keys, "F27 function key",
+        KeyF28 = "kf28", "FI", "key_f28",
+            Keys, "F28 function key",
+        KeyF29 = "kf29", "FJ", "key_f29",
+            Keys, "F29 function key",
+        KeyF30 = "kf30", "FK", "key_f30",
+            Keys, "F30 function key",
+        KeyF31 = "kf31", "FL", "key_f31",
+            Keys, "F31 function key",
+        KeyF32 = "kf32", "FM", "key_f32",
+            Keys, "F32 function key",
+        KeyF33 = "kf33", "FN", "key_f33",
+            Keys, "F33 function key",
+        KeyF34 = "kf34", "FO", "key_f34",
+            Keys, "F34 function key",
+        KeyF35 = "kf35", "FP", "key_f35",
+            Keys, "F35 function key",
+        KeyF36 = "kf36", "FQ", "key_f36",
+            Keys, "F36 function key",
+        KeyF37 = "kf37", "FR", "key_f37",
+            Keys, "F37 function key",
+        KeyF38 = "kf38", "FS", "key_f38",
+            Keys, "F38 function key",
+        KeyF39 = "kf39", "FT", "key_f39",
+            Keys, "F39 function key",
+        KeyF40 = "kf40", "FU", "key_f40",
+            Keys, "F40 function key",
+        KeyF41 = "kf41", "FV", "key_f41",
+            Keys, "F41 function key",
+        KeyF42 = "kf42", "FW", "key_f42",
+            Keys, "F42 function key",
+        KeyF43 = "kf43", "FX", "key_f43",
+            Keys, "F43 function key",
+        KeyF44 = "kf44", "FY", "key_f44",
+            Keys, "F44 function key",
+        KeyF45 = "kf45", "FZ", "key_f45",
+            Keys, "F45 function key",
+        KeyF46 = "kf46", "Fa", "key_f46",
+            Keys, "F46 function key",
+        KeyF47 = "kf47", "Fb", "key_f47",
+            Keys, "F47 function key",
+        KeyF48 = "kf48", "Fc", "key_f48",
+            Keys, "F48 function key",
+        KeyF49 = "kf49", "Fd", "key_f49",
+            Keys, "F49 function key",
+        KeyF50 = "kf50", "Fe", "key_f50",
+            Keys, "F50 function key",
+        KeyF51 = "kf51", "Ff", "key_f51",
+            Keys, "F51 function key",
+        KeyF52 = "kf52", "Fg", "key_f52",
+            Keys, "F52 function key",
+        KeyF53 = "kf53", "Fh", "key_f53",
+            Keys, "F53 function key",
+        KeyF54 = "kf54", "Fi", "key_f54",
+            Keys, "F54 function key",
+        KeyF55 = "kf55", "Fj", "key_f55",
+            Keys, "F55 function key",
+        KeyF56 = "kf56", "Fk", "key_f56",
+            Keys, "F56 function key",
+        KeyF57 = "kf57", "Fl", "key_f57",
+            Keys, "F57 function key",
+        KeyF58 = "kf58", "Fm", "key_f58",
+            Keys, "F58 function key",
+        KeyF59 = "kf59", "Fn", "key_f59",
+            Keys, "F59 function key",
+        KeyF60 = "kf60", "Fo", "key_f60",
+            Keys, "F60 function key",
+        KeyF61 = "kf61", "Fp", "key_f61",
+            Keys, "F61 function key",
+        KeyF62 = "kf62", "Fq", "key_f62",
+            Keys, "F62 function key",
+        KeyF63 = "kf63", "Fr", "key_f63",
+            Keys, "F63 function key",
+        ClrBol = "el1", "cb", "clr_bol",
+            Editing, "clear to beginning of line",
+        ClearMargins = "mgc", "MC", "clear_margins",
+            Margins, "clear right and left soft margins",
+        SetLeftMargin = "smgl", "ML", "set_left_margin",
+            Margins, "set left soft margin at current column",
+        SetRightMargin = "smgr", "MR", "set_right_margin",
+            Margins, "set right soft margin at current column",
+        LabelFormat = "fln", "Lf", "label_format",
+            Labels, "label format",
+        SetClock = "sclk", "SC", "set_clock",
+            Other, "set clock, #1 hrs #2 mins #3 secs",
+        DisplayClock = "dclk", "DK", "display_clock",
+            Other, "display clock",
+        RemoveClock = "rmclk", "RC", "remove_clock",
+            Other, "remove clock",
+        CreateWindow = "cwin", "CW", "create_window",
+            Other, "define a window #1 from #2,#3 to #4,#5",
+        GotoWindow = "wingo", "WG", "goto_window",
+            Other, "go to window #1",
+        Hangup = "hup", "HU", "hangup",
+            Dialing, "hang-up phone",
+        DialPhone = "dial", "DI", "dial_phone",
+            Dialing, "dial number #1",
+        QuickDial = "qdial", "QD", "quick_dial",
+            Dialing, "dial number #1 without checking",
+        Tone = "tone", "TO", "tone",
+            Dialing, "select touch tone dialing",
+        Pulse = "pulse", "PU", "pulse",
+            Dialing, "select pulse dialing",
+        FlashHook = "hook", "fh", "flash_hook",
+            Dialing, "flash switch hook",
+        FixedPause = "pause", "PA", "fixed_pause",
+            Dialing, "pause for 2-3 seconds",
+        WaitTone = "wait", "WA", "wait_tone",
+            Dialing, "wait for dial-tone",
+        User0 = "u0", "u0", "user0",
+            Other, "user string #0",
+        User1 = "u1", "u1", "user1",
+            Other, "user string #1",
+        User2 = "u2", "u2", "user2",
+            Other, "user string #2",
+        User3 = "u3", "u3", "user3",
+            Other, "user string #3",
+        User4 = "u4", "u4", "user4",
+            Other, "user string #4",
+        User5 = "u5", "u5", "user5",
+            Other, "user string #5",
+        User6 = "u6", "u6", "user6",
+            Other, "user string #6",
+        User7 = "u7", "u7", "user7",
+            Other, "user string #7",
+        User8 = "u8", "u8", "user8",
+            Other, "user string #8",
+        User9 = "u9", "u9", "user9",
+            Other, "user string #9",
+        OrigPair = "op", "op", "orig_pair",
+            Colors, "set default pair to its original value",
+        OrigColors = "oc", "oc", "orig_colors",
+            Colors, "set all color pairs to the original ones",
+        InitializeColor = "initc", "Ic", "initialize_color",
+            Colors, "initialize color #1 to (#2,#3,#4)",
+        InitializePair = "initp", "Ip", "initialize_pair",
+            Colors, "initialize color pair #1 to fg=(#2,#3,#4), bg=(#5,#6,#7)",
+        SetColorPair = "scp", "sp", "set_color_pair",
+            Colors, "set current color pair to #1",
+        SetForeground = "setf", "Sf", "set_foreground",
+            Colors, "set foreground color #1",
+        SetBackground = "setb", "Sb", "set_background",
+            Colors, "set background color #1",
+        ChangeCharPitch = "cpi", "ZA", "change_char_pitch",
+            Printer, "change number of characters per inch to #1",
+        ChangeLinePitch = "lpi", "ZB", "change_line_pitch",
+            Printer, "change number of lines per inch to #1",
+        ChangeResHorz = "chr", "ZC", "change_res_horz",
+            Printer, "change horizontal resolution to #1",
+        ChangeResVert = "cvr", "ZD", "change_res_vert",
+            Printer, "change vertical resolution to #1",
+        DefineChar = "defc", "ZE", "define_char",
+            Printer, "define a character #1, #2 dots wide, descender #3",
+        EnterDoublewideMode = "swidm", "ZF", "enter_doublewide_mode",
+            Printer, "enter double-wide mode",
+        EnterDraftQuality = "sdrfq", "ZG", "enter_draft_quality",
+            Printer, "enter draft-quality mode",
+        EnterItalicsMode = "sitm", "ZH", "enter_italics_mode",
+            Attributes, "enter italic mode",
+        EnterLeftwardMode = "slm", "ZI", "enter_leftward_mode",
+            Printer, "start leftward carriage motion",
+        EnterMicroMode = "smicm", "ZJ", "enter_micro_mode",
+            Printer, "start micro-motion mode",
+        EnterNearLetterQuality = "snlq", "ZK", "enter_near_letter_quality",
+            Printer, "enter NLQ mode",
+        EnterNormalQuality = "snrmq", "ZL", "enter_normal_quality",
+            Printer, "enter normal-quality mode",
+        EnterShadowMode = "sshm", "ZM", "enter_shadow_mode",
+            Printer, "enter shadow-print mode",
+        EnterSubscriptMode = "ssubm", "ZN", "enter_subscript_mode",
+            Printer, "enter subscript mode",
+        EnterSuperscriptMode = "ssupm", "ZO", "enter_superscript_mode",
+            Printer, "enter superscript mode",
+        EnterUpwardMode = "sum", "ZP", "enter_upward_mode",
+            Printer, "start upward carriage motion",
+        ExitDoublewideMode = "rwidm", "ZQ", "exit_doublewide_mode",
+            Printer, "end double-wide mode",
+        ExitItalicsMode = "ritm", "ZR", "exit_italics_mode",
+            Attributes, "end italic mode",
+        ExitLeftwardMode = "rlm", "ZS", "exit_leftward_mode",
+            Printer, "end left-motion mode",
+        ExitMicroMode = "rmicm", "ZT", "exit_micro_mode",
+            Printer, "end micro-motion mode",
+        ExitShadowMode = "rshm", "ZU", "exit_shadow_mode",
+            Printer, "end shadow-print mode",
+        ExitSubscriptMode = "rsubm", "ZV", "exit_subscript_mode",
+            Printer, "end subscript mode",
+        ExitSuperscriptMode = "rsupm", "ZW", "exit_superscript_mode",
+            Printer, "end superscript mode",
+        ExitUpwardMode = "rum", "ZX", "exit_upward_mode",
+            Printer, "end reverse character motion",
+        MicroColumnAddress = "mhpa", "ZY", "micro_column_address",
+            Printer, "like column_address in micro mode",
+        MicroDown = "mcud1", "ZZ", "micro_down",
+            Printer, "like cursor_down in micro mode",
+        MicroLeft = "mcub1", "Za", "micro_left",
+            Printer, "like cursor_left in micro mode",
+        MicroRight = "mcuf1", "Zb", "micro_right",
+            Printer, "like cursor_right in micro mode",
+        MicroRowAddress = "mvpa", "Zc", "micro_row_address",
+            Printer, "like row_address #1 in micro mode",
+        MicroUp = "mcuu1", "Zd", "micro_up",
+            Printer, "like cursor_up in micro mode",
+        OrderOfPins = "porder", "Ze", "order_of_pins",
+            Printer, "match software bits to print-head pins",
+        ParmDownMicro = "mcud", "Zf", "parm_down_micro",
+            Printer, "like parm_down_cursor in micro mode",
+        ParmLeftMicro = "mcub", "Zg", "parm_left_micro",
+            Printer, "like parm_left_cursor in micro mode",
+        ParmRightMicro = "mcuf", "Zh", "parm_right_micro",
+            Printer, "like parm_right_cursor in micro mode",
+        ParmUpMicro = "mcuu", "Zi", "parm_up_micro",
+            Printer, "like parm_up_cursor in micro mode",
+        SelectCharSet = "scs", "Zj", "select_char_set",
+            Charset, "select character set, #1",
+        SetBottomMargin = "smgb", "Zk", "set_bottom_margin",
+            Margins, "set bottom margin at current line",
+        SetBottomMarginParm = "smgbp", "Zl", "set_bottom_margin_parm",
+            Margins, "set bottom margin at line #1 or (if smgtp is not given) #2 lines from bottom",
+        SetLeftMarginParm = "smglp", "Zm", "set_left_margin_parm",
+            Margins, "set left (right) margin at column #1",
+        SetRightMarginParm = "smgrp", "Zn", "set_right_margin_parm",
+            Margins, "set right margin at column #1",
+        SetTopMargin = "smgt", "Zo", "set_top_margin",
+            Margins, "set top margin at current line",
+        SetTopMarginParm = "smgtp", "Zp", "set_top_margin_parm",
+            Margins, "set top (bottom) margin at row #1",
+        StartBitImage = "sbim", "Zq", "start_bit_image",
+            Printer, "start printing bit image graphics",
+        StartCharSetDef = "scsd", "Zr", "start_char_set_def",
+            Printer, "start character set definition #1, with #2 characters in the set",
+        StopBitImage = "rbim", "Zs", "stop_bit_image",
+            Printer, "stop printing bit image graphics",
+        StopCharSetDef = "rcsd", "Zt", "stop_char_set_def",
+            Printer, "end definition of character set #1",
+        SubscriptCharacters = "subcs", "Zu", "subscript_characters",
+            Printer, "list of subscriptable characters",
+        SuperscriptCharacters = "supcs", "Zv", "superscript_characters",
+            Printer, "list of superscriptable characters",
+        TheseCauseCr = "docr", "Zw", "these_cause_cr",
+            Printer, "printing any of these characters causes CR",
+        ZeroMotion = "zerom", "Zx", "zero_motion",
+            Printer, "no motion for subsequent character",
+        CharSetNames = "csnm", "Zy", "char_set_names",
+            Charset, "produce #1'th item from list of character set names",
+        KeyMouse = "kmous", "Km", "key_mouse",
+            Mouse, "mouse event has occurred",
+        MouseInfo = "minfo", "Mi", "mouse_info",
+            Mouse, "mouse status information",
+        ReqMousePos = "reqmp", "RQ", "req_mouse_pos",
+            Mouse, "request mouse position",
+        GetMouse = "getm", "Gm", "get_mouse",
+            Mouse, "curses should get button events, parameter #1 not documented",
+        SetAForeground = "setaf", "AF", "set_a_foreground",
+            Colors, "set foreground color to #1, using ANSI escape",
+        SetABackground = "setab", "AB", "set_a_background",
+            Colors, "set background color to #1, using ANSI escape",
+        PkeyPlab = "pfxl", "xl", "pkey_plab",
+            Keys, "program function key #1 to type string #2 and show string #3",
+        DeviceType = "devt", "dv", "device_type",
+            Charset, "indicate language/codeset support",
+        CodeSetInit = "csin", "ci", "code_set_init",
+            Charset, "init sequence for multiple codesets",
+        Set0DesSeq = "s0ds", "s0", "set0_des_seq",
+            Charset, "shift to codeset 0 (EUC set 0, ASCII)",
+        Set1DesSeq = "s1ds", "s1", "set1_des_seq",
+            Charset, "shift to codeset 1",
+        Set2DesSeq = "s2ds", "s2", "set2_des_seq",
+            Charset, "shift to codeset 2",
+        Set3DesSeq = "s3ds", "s3", "set3_des_seq",
+            Charset, "shift to codeset 3",
+        SetLrMargin = "smglr", "ML", "set_lr_margin",
+            Margins, "set both left and right margins to #1, #2",
+        SetTbMargin = "smgtb", "MT", "set_tb_margin",
+            Margins, "sets both top and bottom margins to #1, #2",
+        BitImageRepeat = "birep", "Xy", "bit_image_repeat",
+            Printer, "repeat bit image cell #1 #2 times",
+        BitImageNewline = "binel", "Zz", "bit_image_newline",
+            Printer, "move to next row of the bit image",
+        BitImageCarriageReturn = "bicr", "Yv", "bit_image_carriage_return",
+            Printer, "move to beginning of same row",
+        ColorNames = "colornm", "Yw", "color_names",
+            Colors, "give name for color #1",
+        DefineBitImageRegion = "defbi", "Yx", "define_bit_image_region",
+            Printer, "define rectangular bit image region",
+        EndBitImageRegion = "endbi", "Yy", "end_bit_image_region",
+            Printer, "end a bit-image region",
+        SetColorBand = "setcolor", "Yz", "set_color_band",
+            Printer, "change to ribbon color #1",
+        SetPageLength = "slines", "YZ", "set_page_length",
+            Printer, "set page length to #1 lines",
+        DisplayPcChar = "dispc", "S1", "display_pc_char",
+            Charset, "display PC character #1",
+        EnterPcCharsetMode = "smpch", "S2", "enter_pc_charset_mode",
+            Charset, "enter PC character display mode",
+        ExitPcCharsetMode = "rmpch", "S3", "exit_pc_charset_mode",
+            Charset, "exit PC character display mode",
+        EnterScancodeMode = "smsc", "S4", "enter_scancode_mode",
+            Charset, "enter PC scancode mode",
+        ExitScancodeMode = "rmsc", "S5", "exit_scancode_mode",
+            Charset, "exit PC scancode mode",
+        PcTermOptions = "pctrm", "S6", "pc_term_options",
+            Charset, "PC terminal options",
+        ScancodeEscape = "scesc", "S7", "scancode_escape",
+            Charset, "escape for scancode emulation",
+        AltScancodeEsc = "scesa", "S8", "alt_scancode_esc",
+            Charset, "alternate escape for scancode emulation",
+        EnterHorizontalHlMode = "ehhlm", "Xh", "enter_horizontal_hl_mode",
+            Attributes, "enter horizontal highlight mode",
+        EnterLeftHlMode = "elhlm", "Xl", "enter_left_hl_mode",
+            Attributes, "enter left highlight mode",
+        EnterLowHlMode = "elohlm", "Xo", "enter_low_hl_mode",
+            Attributes, "enter low highlight mode",
+        EnterRightHlMode = "erhlm", "Xr", "enter_right_hl_mode",
+            Attributes, "enter right highlight mode",
+        EnterTopHlMode = "ethlm", "Xt", "enter_top_hl_mode",
+            Attributes, "enter top highlight mode",
+        EnterVerticalHlMode = "evhlm", "Xv", "enter_vertical_hl_mode",
+            Attributes, "enter vertical highlight mode",
+        SetAAttributes = "sgr1", "sA", "set_a_attributes",
+            Attributes, "define second set of video attributes #1-#6",
+        SetPglenInch = "slength", "YI", "set_pglen_inch",
+            Printer, "set page length to #1 hundredth of an inch",
+        TermcapInit2 = "OTi2", "i2", "termcap_init2",
+            Init, "secondary initialization string",
+        TermcapReset = "OTrs", "rs", "termcap_reset",
+            Init, "terminal reset string",
+        LinefeedIfNotLf = "OTnl", "nl", "linefeed_if_not_lf",
+            Cursor, "use to move down",
+        BackspaceIfNotBs = "OTbc", "bc", "backspace_if_not_bs",
+            Cursor, "move left, if not ^H",
+        OtherNonFunctionKeys = "OTko", "ko", "other_non_function_keys",
+            Keys, "list of self-mapped keycaps",
+        ArrowKeyMap = "OTma", "ma", "arrow_key_map",
+            Keys, "map motion-keys for vi version 2",
+        AcsUlcorner = "OTG2", "G2", "acs_ulcorner",
+            Charset, "single upper left",
+        AcsLlcorner = "OTG3", "G3", "acs_llcorner",
+            Charset, "single lower left",
+        AcsUrcorner = "OTG1", "G1", "acs_urcorner",
+            Charset, "single upper right",
+        AcsLrcorner = "OTG4", "G4", "acs_lrcorner",
+            Charset, "single lower right",
+        AcsLtee = "OTGR", "GR", "acs_ltee",
+            Charset, "tee pointing right",
+        AcsRtee = "OTGL", "GL", "acs_rtee",
+            Charset, "tee pointing left",
+        AcsBtee = "OTGU", "GU", "acs_btee",
+            Charset, "tee pointing up",
+        AcsTtee = "OTGD", "GD", "acs_ttee",
+            Charset, "tee pointing down",
+        AcsHline = "OTGH", "GH", "acs_hline",
+            Charset, "single horizontal line",
+        AcsVline = "OTGV", "GV", "acs_vline",
+            Charset, "single vertical line",
+        AcsPlus = "OTGC", "GC", "acs_plus",
+            Charset, "single intersection",
+        MemoryLock = "meml", "ml", "memory_lock",
+            Other, "lock memory above cursor",
+        MemoryUnlock = "memu", "mu", "memory_unlock",
+            Other, "unlock memory",
+        BoxChars1 = "box1", "bx", "box_chars_1",
+            Charset, "box characters primary set",
+    }
+}
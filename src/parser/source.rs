@@ -0,0 +1,474 @@
+//! Terminfo source format parsing (the input to `tic`, see terminfo(5))
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+
+use {BoolCap, NumberCap, StringCap, Terminfo};
+
+/// An error from parsing terminfo source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The line the error was found on, starting at 1.
+    pub line: usize,
+    /// The column (in characters) the error was found at, starting at 1.
+    pub column: usize,
+    /// What went wrong.
+    pub kind: ErrorKind,
+}
+
+/// The kind of error encountered while parsing terminfo source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// An indented line appeared before the first entry.
+    NoEntry,
+    /// The names field of an entry was empty.
+    ShortNames,
+    /// A capability name was empty.
+    EmptyName,
+    /// A numeric value was not a valid decimal, octal or hexadecimal number.
+    InvalidNumber,
+    /// A string value ended in the middle of an escape sequence.
+    UnterminatedEscape,
+    /// Unexpected characters followed a capability cancellation (`name@`).
+    TrailingCancel,
+    /// A standard capability was given a value of the wrong type.
+    WrongType(String),
+    /// An entry inherits (`use=`) from an entry that could not be found.
+    UnknownUse(String),
+    /// An entry inherits from itself, directly or indirectly.
+    UseLoop(String),
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ErrorKind::*;
+        match *self {
+            NoEntry => f.write_str("capabilities outside of an entry"),
+            ShortNames => f.write_str("no names exposed, need at least one"),
+            EmptyName => f.write_str("empty capability name"),
+            InvalidNumber => f.write_str("invalid number"),
+            UnterminatedEscape => f.write_str("unterminated escape sequence"),
+            TrailingCancel => f.write_str("unexpected characters after cancellation"),
+            WrongType(ref name) => write!(f, "wrong value type for capability {}", name),
+            UnknownUse(ref name) => write!(f, "use of unknown entry {}", name),
+            UseLoop(ref name) => write!(f, "entry {} uses itself", name),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl ::std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Value {
+    Bool,
+    Number(u32),
+    String(Vec<u8>),
+    Cancel,
+}
+
+#[derive(Debug)]
+pub(super) struct Entry {
+    pub(super) names: Vec<String>,
+    /// Capabilities in the order they appear, with their byte offsets in the source.
+    pub(super) caps: Vec<(String, Value, usize)>,
+    /// Entries to inherit from, with their byte offsets in the source.
+    pub(super) uses: Vec<(String, usize)>,
+}
+
+/// Parse every entry in a terminfo source file.
+///
+/// `use=` capabilities may only refer to entries defined in the same source.
+pub fn parse(source: &str) -> Result<Vec<Terminfo>, Error> {
+    parse_with(source, &mut |_| None)
+}
+
+/// Parse every entry in a terminfo source file.
+///
+/// `use=` capabilities referring to entries not defined in the source are resolved with
+/// `lookup`, for example with `Terminfo::from_name`.
+pub fn parse_with(source: &str,
+                  lookup: &mut dyn FnMut(&str) -> Option<Terminfo>)
+                  -> Result<Vec<Terminfo>, Error> {
+    let entries = split_entries(source)?
+                      .into_iter()
+                      .map(|fields| parse_entry(source, fields))
+                      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut resolver = Resolver::new(source, &entries, lookup);
+    (0..entries.len()).map(|i| resolver.resolve(i)).collect()
+}
+
+pub(super) fn error(source: &str, offset: usize, kind: ErrorKind) -> Error {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Error {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+        kind,
+    }
+}
+
+/// Split the source into entries, each a list of comma-separated fields with their offsets.
+fn split_entries(source: &str) -> Result<Vec<Vec<(usize, &str)>>, Error> {
+    let mut entries: Vec<Vec<(usize, &str)>> = Vec::new();
+    let mut offset = 0;
+    for line in source.split('\n') {
+        let line_offset = offset;
+        offset += line.len() + 1;
+
+        let line = line.trim_end_matches('\r');
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            entries.push(Vec::new());
+        }
+        let fields = match entries.last_mut() {
+            Some(fields) => fields,
+            None => return Err(error(source, line_offset, ErrorKind::NoEntry)),
+        };
+
+        // Split on unescaped commas.
+        let mut start = 0;
+        let mut prev = None;
+        let mut chars = line.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                // Like ncurses, treat "%^" as the XOR operator rather than a control character.
+                '\\' => {
+                    chars.next();
+                }
+                '^' if prev != Some('%') => {
+                    chars.next();
+                }
+                ',' => {
+                    fields.push((line_offset + start, &line[start..i]));
+                    start = i + 1;
+                }
+                _ => (),
+            }
+            prev = Some(c);
+        }
+        // Tolerate a missing comma after the last field.
+        if !line[start..].trim().is_empty() {
+            fields.push((line_offset + start, &line[start..]));
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_entry(source: &str, fields: Vec<(usize, &str)>) -> Result<Entry, Error> {
+    let mut fields = fields.into_iter().map(|(offset, field)| {
+        let trimmed = field.trim_start();
+        (offset + field.len() - trimmed.len(), trimmed)
+    });
+
+    // Every entry starts with a non-empty line, so there is always a names field.
+    let (names_offset, names) = fields.next().expect("entry without fields");
+    if names.trim().is_empty() {
+        return Err(error(source, names_offset, ErrorKind::ShortNames));
+    }
+    let names = names.trim().split('|').map(|s| s.to_owned()).collect();
+
+    let mut entry = Entry {
+        names,
+        caps: Vec::new(),
+        uses: Vec::new(),
+    };
+
+    for (offset, field) in fields {
+        // A leading period comments out the capability.
+        if field.is_empty() || field.starts_with('.') {
+            continue;
+        }
+        // Only string values may end in whitespace.
+        let split = field.find(['#', '=', '@']).unwrap_or(field.len());
+        let field = if field[split..].starts_with('=') {
+            field
+        } else {
+            field.trim_end()
+        };
+        let split = split.min(field.len());
+        let (name, rest) = field.split_at(split);
+        if name.is_empty() {
+            return Err(error(source, offset, ErrorKind::EmptyName));
+        }
+        let value_offset = offset + split + 1;
+        let value = match rest.chars().next() {
+            None => Value::Bool,
+            Some('@') if rest.len() == 1 => Value::Cancel,
+            Some('@') => return Err(error(source, value_offset, ErrorKind::TrailingCancel)),
+            Some('#') => {
+                match parse_number(&rest[1..]) {
+                    Some(n) => Value::Number(n),
+                    None => return Err(error(source, value_offset, ErrorKind::InvalidNumber)),
+                }
+            }
+            Some(_) => {
+                let value = unescape(&rest[1..]).map_err(|i| {
+                    error(source, value_offset + i, ErrorKind::UnterminatedEscape)
+                })?;
+                if name == "use" {
+                    entry.uses.push((String::from_utf8_lossy(&value).into_owned(), offset));
+                    continue;
+                }
+                Value::String(value)
+            }
+        };
+        entry.caps.push((name.to_owned(), value, offset));
+    }
+    Ok(entry)
+}
+
+/// Parse a number in C syntax: decimal, octal with a leading `0`, or hexadecimal with `0x`.
+pub(super) fn parse_number(s: &str) -> Option<u32> {
+    if s.starts_with("0x") || s.starts_with("0X") {
+        u32::from_str_radix(&s[2..], 16).ok()
+    } else if s.len() > 1 && s.starts_with('0') {
+        u32::from_str_radix(&s[1..], 8).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Decode the escapes in a string value.
+///
+/// On failure, returns the offset of the incomplete escape sequence.
+pub(super) fn unescape(s: &str) -> Result<Vec<u8>, usize> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        i += 1;
+        match bytes[start] {
+            b'\\' => {
+                let c = *bytes.get(i).ok_or(start)?;
+                i += 1;
+                out.push(match c {
+                    b'E' | b'e' => b'\x1b',
+                    b'n' | b'l' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => b'\x08',
+                    b'f' => b'\x0c',
+                    b's' => b' ',
+                    b'a' => b'\x07',
+                    b'0'..=b'7' => {
+                        let mut n = (c - b'0') as u32;
+                        let mut digits = 1;
+                        while digits < 3 {
+                            match bytes.get(i) {
+                                Some(&d @ b'0'..=b'7') => n = n * 8 + (d - b'0') as u32,
+                                _ => break,
+                            }
+                            i += 1;
+                            digits += 1;
+                        }
+                        // NUL terminates compiled strings, so it is written as \200 instead.
+                        match n as u8 {
+                            0 => 0o200,
+                            n => n,
+                        }
+                    }
+                    // Everything else, including \^ \\ \, and \:, stands for itself.
+                    c => c,
+                });
+            }
+            b'^' if start == 0 || bytes[start - 1] != b'%' => {
+                let c = *bytes.get(i).ok_or(start)?;
+                i += 1;
+                out.push(match c {
+                    b'?' => 0x7f,
+                    c => c & 0x1f,
+                });
+            }
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Resolves the inheritance of parsed entries.
+pub(super) struct Resolver<'a, 'b> {
+    source: &'a str,
+    entries: &'a [Entry],
+    /// `None` if not resolved yet, `Some(None)` while being resolved.
+    resolved: Vec<Option<Option<Terminfo>>>,
+    lookup: &'b mut dyn FnMut(&str) -> Option<Terminfo>,
+}
+
+impl<'a, 'b> Resolver<'a, 'b> {
+    pub(super) fn new(source: &'a str,
+                      entries: &'a [Entry],
+                      lookup: &'b mut dyn FnMut(&str) -> Option<Terminfo>)
+                      -> Resolver<'a, 'b> {
+        Resolver {
+            source,
+            entries,
+            resolved: vec![None; entries.len()],
+            lookup,
+        }
+    }
+
+    pub(super) fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.names.iter().any(|n| n == name))
+    }
+
+    pub(super) fn resolve(&mut self, i: usize) -> Result<Terminfo, Error> {
+        match self.resolved[i] {
+            Some(Some(ref info)) => return Ok(info.clone()),
+            Some(None) => unreachable!("use loops are detected by the caller"),
+            None => (),
+        }
+        self.resolved[i] = Some(None);
+
+        let entries = self.entries;
+        let entry = &entries[i];
+        let mut info = Terminfo::new(entry.names.clone());
+        let mut cancelled = HashSet::new();
+
+        for &(ref name, ref value, offset) in &entry.caps {
+            let wrong_type = || error(self.source, offset, ErrorKind::WrongType(name.clone()));
+            let is_bool = BoolCap::from_name(name).is_some();
+            let is_number = NumberCap::from_name(name).is_some();
+            let is_string = StringCap::from_name(name).is_some();
+            match *value {
+                Value::Bool if is_number || is_string => return Err(wrong_type()),
+                Value::Number(_) if is_bool || is_string => return Err(wrong_type()),
+                Value::String(_) if is_bool || is_number => return Err(wrong_type()),
+                Value::Bool => info.set_bool(name, true),
+                Value::Number(n) => info.set_number(name, Some(n)),
+                Value::String(ref s) => info.set_string(name, Some(s)),
+                Value::Cancel => {
+                    cancelled.insert(&name[..]);
+                }
+            }
+        }
+
+        for &(ref name, offset) in &entry.uses {
+            let parent = match self.find(name) {
+                Some(j) if matches!(self.resolved[j], Some(None)) => {
+                    return Err(error(self.source, offset, ErrorKind::UseLoop(name.clone())))
+                }
+                Some(j) => self.resolve(j)?,
+                None => {
+                    match (self.lookup)(name) {
+                        Some(parent) => parent,
+                        None => {
+                            return Err(error(self.source,
+                                             offset,
+                                             ErrorKind::UnknownUse(name.clone())))
+                        }
+                    }
+                }
+            };
+            // Capabilities set or cancelled in the entry itself, or inherited from an earlier
+            // `use=`, take precedence.
+            for name in parent.bools() {
+                if !cancelled.contains(name) {
+                    info.set_bool(name, true);
+                }
+            }
+            for (name, n) in parent.numbers() {
+                if !cancelled.contains(name) && info.number(name).is_none() {
+                    info.set_number(name, Some(n));
+                }
+            }
+            for (name, s) in parent.strings() {
+                if !cancelled.contains(name) && info.string(name).is_none() {
+                    info.set_string(name, Some(s));
+                }
+            }
+        }
+
+        self.resolved[i] = Some(Some(info.clone()));
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, ErrorKind};
+
+    #[test]
+    fn test_multiline() {
+        let entries = parse("# A comment\n\
+                             foo|foo-alias|Foo terminal,\n\
+                             \tam, cols#80,\n\
+                             \t.bw, lines#0x18, it#010,\n\
+                             \tbel=^G, cup=\\E[%i%p1%d;%p2%dH,\n\
+                             \n\
+                             bar|Bar terminal,\n\
+                             \txon, Tc, Ss=\\E[%p1%d q,\n")
+                          .unwrap();
+        assert_eq!(entries.len(), 2);
+        let foo = &entries[0];
+        assert_eq!(foo.names, ["foo", "foo-alias", "Foo terminal"]);
+        assert!(foo.bool("am"));
+        assert!(!foo.bool("bw"));
+        assert_eq!(foo.number("cols"), Some(80));
+        assert_eq!(foo.number("lines"), Some(24));
+        assert_eq!(foo.number("it"), Some(8));
+        assert_eq!(foo.string("bel"), Some(&b"\x07"[..]));
+        assert_eq!(foo.string("cup"), Some(&b"\x1b[%i%p1%d;%p2%dH"[..]));
+        let bar = &entries[1];
+        assert!(bar.bool("Tc"));
+        assert_eq!(bar.string("Ss"), Some(&b"\x1b[%p1%d q"[..]));
+    }
+
+    #[test]
+    fn test_escapes() {
+        let entries = parse("t|test,\n\tu0=\\e\\n\\l\\r\\t\\b\\f\\s\\^\\\\\\,\\:,\n\
+                             \tu1=\\072\\0\\000\\1770^@^?^[,\n\
+                             \tu2=%p1%{3}%^%d,\n")
+                          .unwrap();
+        assert_eq!(entries[0].string("u0"), Some(&b"\x1b\n\n\r\t\x08\x0c ^\\,:"[..]));
+        assert_eq!(entries[0].string("u1"), Some(&b":\x80\x80\x7f0\x00\x7f\x1b"[..]));
+        assert_eq!(entries[0].string("u2"), Some(&b"%p1%{3}%^%d"[..]));
+    }
+
+    #[test]
+    fn test_use() {
+        let entries = parse("base|Base,\n\tam, xon, cols#80, bel=^G, el=\\E[K,\n\
+                             child|Child,\n\tcols#132, xon@, el@, use=base,\n")
+                          .unwrap();
+        let child = &entries[1];
+        assert!(child.bool("am"));
+        assert!(!child.bool("xon"));
+        assert_eq!(child.number("cols"), Some(132));
+        assert_eq!(child.string("el"), None);
+        assert_eq!(child.string("bel"), Some(&b"\x07"[..]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = parse("t|test,\n\tam,\n\tcols#eighty,\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind), (3, 7, ErrorKind::InvalidNumber));
+        let e = parse("t|test,\n\tam, cup#1,\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind),
+                   (2, 6, ErrorKind::WrongType("cup".to_owned())));
+        let e = parse("t|test,\n\tuse=missing,\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind),
+                   (2, 2, ErrorKind::UnknownUse("missing".to_owned())));
+        let e = parse("a|A,\n\tuse=b,\nb|B,\n\tuse=a,\n").unwrap_err();
+        assert_eq!((e.line, e.column), (4, 2));
+        let e = parse("\tam,\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind), (1, 1, ErrorKind::NoEntry));
+        let e = parse("t|test,\n\tbel=\\").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind), (2, 6, ErrorKind::UnterminatedEscape));
+    }
+}
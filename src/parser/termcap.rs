@@ -0,0 +1,396 @@
+//! Termcap file parsing (`/etc/termcap` and `$TERMCAP`, see termcap(5))
+//!
+//! Termcap entries are translated to terminfo ones the way `tic` does: two-letter codes become
+//! the matching standard capabilities, leading padding becomes a mandatory `$<..>` delay, and
+//! percent codes are translated with `parm::termcap_to_terminfo`. Codes that aren't standard are
+//! kept as extended capabilities.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use parm;
+use parser::source::{error, parse_number, unescape, Entry, Resolver, Value};
+pub use parser::source::{Error, ErrorKind};
+use {BoolCap, NumberCap, Source, StringCap, Terminfo};
+
+/// The files searched for an entry when `$TERMCAP` doesn't name one.
+pub const DEFAULT_FILES: &[&str] = &["/etc/termcap", "/usr/share/misc/termcap"];
+
+/// The deepest `tc=` chain followed when looking entries up in files.
+const MAX_DEPTH: usize = 32;
+
+/// Parse every entry in a termcap file.
+///
+/// `tc=` capabilities may only refer to entries defined in the same source.
+pub fn parse(source: &str) -> Result<Vec<Terminfo>, Error> {
+    parse_with(source, &mut |_| None)
+}
+
+/// Parse every entry in a termcap file.
+///
+/// `tc=` capabilities referring to entries not defined in the source are resolved with
+/// `lookup`.
+pub fn parse_with(source: &str,
+                  lookup: &mut dyn FnMut(&str) -> Option<Terminfo>)
+                  -> Result<Vec<Terminfo>, Error> {
+    let entries = parse_entries(source)?;
+    let mut resolver = Resolver::new(source, &entries, lookup);
+    (0..entries.len()).map(|i| resolver.resolve(i).map(postprocess)).collect()
+}
+
+/// Find the entry for `term` like termcap's `tgetent`, given the value of `$TERMCAP`.
+///
+/// If `termcap` is an absolute path, the entry is looked up in that file. If it is an entry
+/// for `term`, it is used, and the entries it inherits from are looked up in the default
+/// files. Otherwise the entry is looked up in the default files.
+pub fn lookup(term: &str, termcap: Option<&OsStr>) -> io::Result<Terminfo> {
+    let files: Vec<&Path> = match termcap {
+        Some(path) if path.to_string_lossy().starts_with('/') => vec![Path::new(path)],
+        _ => DEFAULT_FILES.iter().map(Path::new).collect(),
+    };
+    if let Some(source) = termcap.map(OsStr::to_string_lossy) {
+        if !source.starts_with('/') {
+            let entries = parse_entries(&source)?;
+            if let Some(i) = entries.iter().position(|e| e.names.iter().any(|n| n == term)) {
+                let mut lookup = |name: &str| find(&files, name, 1).ok();
+                let info = Resolver::new(&source, &entries, &mut lookup).resolve(i)?;
+                return Ok(postprocess(info));
+            }
+        }
+    }
+    find(&files, term, 0).map(postprocess)
+}
+
+/// Find the entry for `term`, like `lookup` with the value of `$TERMCAP`.
+pub fn from_env(term: &str) -> io::Result<Terminfo> {
+    lookup(term, env::var_os("TERMCAP").as_deref())
+}
+
+/// Find an entry in the first of `files` that has it, without postprocessing it.
+fn find(files: &[&Path], term: &str, depth: usize) -> io::Result<Terminfo> {
+    for &path in files {
+        let source = match fs::read(path) {
+            Ok(source) => String::from_utf8_lossy(&source).into_owned(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let entries = parse_entries(&source)?;
+        if let Some(i) = entries.iter().position(|e| e.names.iter().any(|n| n == term)) {
+            let mut lookup = |name: &str| if depth < MAX_DEPTH {
+                find(files, name, depth + 1).ok()
+            } else {
+                None
+            };
+            let mut info = Resolver::new(&source, &entries, &mut lookup).resolve(i)?;
+            info.source = Source::Termcap(path.to_path_buf());
+            return Ok(info);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::NotFound, "termcap entry not found"))
+}
+
+/// Fill in the standard capabilities that obsolete termcap ones stand for, like `tic`.
+fn postprocess(mut info: Terminfo) -> Terminfo {
+    if info.get(StringCap::CursorLeft).is_none() {
+        if let Some(bc) = info.get(StringCap::BackspaceIfNotBs).map(<[u8]>::to_vec) {
+            info.set_string(StringCap::CursorLeft.name(), Some(&bc));
+        } else if info.contains(BoolCap::BackspacesWithBs) {
+            info.set_string(StringCap::CursorLeft.name(), Some(b"\x08"));
+        }
+    }
+    if info.contains(BoolCap::HasHardwareTabs) {
+        if info.get(StringCap::Tab).is_none() {
+            info.set_string(StringCap::Tab.name(), Some(b"\t"));
+        }
+        if info.get(NumberCap::InitTabs).is_none() {
+            info.set_number(NumberCap::InitTabs.name(), Some(8));
+        }
+    }
+    info
+}
+
+/// Parse the entries of a termcap file, translated to terminfo.
+fn parse_entries(source: &str) -> Result<Vec<Entry>, Error> {
+    split_entries(source).iter().map(|entry| parse_entry(source, entry)).collect()
+}
+
+/// An entry joined into one line, with where each part of it is in the source.
+struct Logical {
+    text: String,
+    /// The offsets in `text` where lines start, and their offsets in the source.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Logical {
+    /// The offset in the source of an offset in `text`.
+    fn offset(&self, pos: usize) -> usize {
+        let i = match self.lines.binary_search_by_key(&pos, |&(start, _)| start) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let (start, offset) = self.lines[i];
+        offset + pos - start
+    }
+}
+
+/// Split the source into entries, joining lines continued with a backslash.
+fn split_entries(source: &str) -> Vec<Logical> {
+    let mut entries: Vec<Logical> = Vec::new();
+    let mut continued = false;
+    let mut offset = 0;
+    for line in source.split('\n') {
+        let mut line_offset = offset;
+        offset += line.len() + 1;
+
+        let mut line = line.trim_end_matches('\r');
+        if continued {
+            let trimmed = line.trim_start();
+            line_offset += line.len() - trimmed.len();
+            line = trimmed;
+        } else if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        } else {
+            entries.push(Logical {
+                text: String::new(),
+                lines: Vec::new(),
+            });
+        }
+        // A backslash escaping another one doesn't continue the line.
+        let backslashes = line.len() - line.trim_end_matches('\\').len();
+        continued = backslashes % 2 == 1;
+        if continued {
+            line = &line[..line.len() - 1];
+        }
+        let entry = entries.last_mut().expect("continued line without an entry");
+        entry.lines.push((entry.text.len(), line_offset));
+        entry.text.push_str(line);
+    }
+    entries
+}
+
+fn parse_entry(source: &str, entry: &Logical) -> Result<Entry, Error> {
+    // Split on unescaped colons.
+    let text = &entry.text;
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut prev = None;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '^' if prev != Some('%') => {
+                chars.next();
+            }
+            ':' => {
+                fields.push((start, &text[start..i]));
+                start = i + 1;
+            }
+            _ => (),
+        }
+        prev = Some(c);
+    }
+    fields.push((start, &text[start..]));
+
+    let mut fields = fields.into_iter();
+    let (names_pos, names) = fields.next().expect("split always yields a field");
+    if names.trim().is_empty() {
+        return Err(error(source, entry.offset(names_pos), ErrorKind::ShortNames));
+    }
+    let mut result = Entry {
+        names: names.trim().split('|').map(|s| s.to_owned()).collect(),
+        caps: Vec::new(),
+        uses: Vec::new(),
+    };
+
+    for (pos, field) in fields {
+        let trimmed = field.trim_start();
+        let pos = pos + field.len() - trimmed.len();
+        // The first character always belongs to the code, as in `@7` and `#1`.
+        let split = trimmed.char_indices()
+                           .skip(1)
+                           .find(|&(_, c)| c == '#' || c == '=' || c == '@')
+                           .map_or(trimmed.len(), |(i, _)| i);
+        let field = if trimmed[split..].starts_with('=') {
+            trimmed
+        } else {
+            trimmed.trim_end()
+        };
+        // A leading period comments the capability out.
+        if field.is_empty() || field.starts_with('.') {
+            continue;
+        }
+        let split = split.min(field.len());
+        let (code, rest) = field.split_at(split);
+        let offset = entry.offset(pos);
+        if code.is_empty() {
+            return Err(error(source, offset, ErrorKind::EmptyName));
+        }
+        let value_pos = pos + split + 1;
+        let name = |standard: Option<&'static str>| -> Result<String, Error> {
+            match standard {
+                Some(name) => Ok(name.to_owned()),
+                None if is_standard(code) => {
+                    Err(error(source, offset, ErrorKind::WrongType(code.to_owned())))
+                }
+                None => Ok(code.to_owned()),
+            }
+        };
+        match rest.chars().next() {
+            None => {
+                let name = name(BoolCap::from_termcap(code).map(BoolCap::name))?;
+                result.caps.push((name, Value::Bool, offset));
+            }
+            Some('@') if rest.len() == 1 => {
+                let names = [BoolCap::from_termcap(code).map(BoolCap::name),
+                             NumberCap::from_termcap(code).map(NumberCap::name),
+                             StringCap::from_termcap(code).map(StringCap::name)];
+                if !is_standard(code) {
+                    result.caps.push((code.to_owned(), Value::Cancel, offset));
+                }
+                for name in names.iter().flatten() {
+                    result.caps.push((name.to_string(), Value::Cancel, offset));
+                }
+            }
+            Some('@') => {
+                return Err(error(source, entry.offset(value_pos), ErrorKind::TrailingCancel));
+            }
+            Some('#') => {
+                let n = match parse_number(&rest[1..]) {
+                    Some(n) => n,
+                    None => {
+                        return Err(error(source,
+                                         entry.offset(value_pos),
+                                         ErrorKind::InvalidNumber))
+                    }
+                };
+                let name = name(NumberCap::from_termcap(code).map(NumberCap::name))?;
+                result.caps.push((name, Value::Number(n), offset));
+            }
+            Some(_) => {
+                let value = &rest[1..];
+                if code == "tc" {
+                    result.uses.push((value.to_owned(), offset));
+                    continue;
+                }
+                let name = name(StringCap::from_termcap(code).map(StringCap::name))?;
+                // The character pairs of `ac` and the label format of `Lf` aren't sequences, so
+                // they have neither padding nor percent codes.
+                let data = code == "ac" || code == "Lf";
+                let (padding, value) = if data { ("", value) } else { split_padding(value) };
+                let value_pos = value_pos + padding.len();
+                let mut value = unescape(value).map_err(|i| {
+                    error(source, entry.offset(value_pos + i), ErrorKind::UnterminatedEscape)
+                })?;
+                // Strings that aren't valid termcap, for example with a literal `%`, are kept
+                // as they are.
+                if !data && value.contains(&b'%') {
+                    if let Ok(translated) = parm::termcap_to_terminfo(&value) {
+                        value = translated;
+                    }
+                }
+                if !padding.is_empty() {
+                    // Termcap delays are always carried out.
+                    value.extend_from_slice(format!("$<{}/>", padding).as_bytes());
+                }
+                result.caps.push((name, Value::String(value), offset));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Whether a code is one of the standard termcap codes, of any type.
+fn is_standard(code: &str) -> bool {
+    BoolCap::from_termcap(code).is_some() || NumberCap::from_termcap(code).is_some() ||
+    StringCap::from_termcap(code).is_some()
+}
+
+/// Split the padding off the start of a string value: a number of milliseconds, possibly with
+/// a decimal point, and a `*` if it is proportional to the number of lines affected.
+fn split_padding(value: &str) -> (&str, &str) {
+    let bytes = value.as_bytes();
+    let mut end = bytes.iter().take_while(|c| c.is_ascii_digit()).count();
+    if end == 0 {
+        return ("", value);
+    }
+    if bytes.get(end) == Some(&b'.') {
+        end += 1 + bytes[end + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+    }
+    if bytes.get(end) == Some(&b'*') {
+        end += 1;
+    }
+    value.split_at(end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, split_padding, ErrorKind};
+
+    #[test]
+    fn test_parse() {
+        let entries = parse("# A comment\n\
+                             ba|base|Base terminal:\\\n\
+                             \t:am:bs:pt:co#80:li#030:\\\n\
+                             \t:cm=5\\E[%i%d;%dH:cl=50*\\E[2J:ce=2.5\\E[K:\\\n\
+                             \t:bl=^G:kb=\\177:is=\\072\\E\\:^[:xx=%%%q:Q9#3:#1=\\E?:\\\n\
+                             \t:ac=0a``%n:..sa=\\E[%p1%dm:ML=\\E[%i%d;%ds:\n\
+                             \n\
+                             ch|child:co#132:am@:bs@:tc=base:\n")
+                          .unwrap();
+        let base = &entries[0];
+        assert_eq!(base.names, ["ba", "base", "Base terminal"]);
+        assert!(base.bool("am"));
+        assert!(base.bool("OTbs"));
+        assert_eq!(base.number("cols"), Some(80));
+        assert_eq!(base.number("lines"), Some(24));
+        assert_eq!(base.string("cup"), Some(&b"\x1b[%i%p1%d;%p2%dH$<5/>"[..]));
+        assert_eq!(base.string("clear"), Some(&b"\x1b[2J$<50*/>"[..]));
+        assert_eq!(base.string("el"), Some(&b"\x1b[K$<2.5/>"[..]));
+        assert_eq!(base.string("bel"), Some(&b"\x07"[..]));
+        assert_eq!(base.string("kbs"), Some(&b"\x7f"[..]));
+        assert_eq!(base.string("is2"), Some(&b":\x1b:\x1b"[..]));
+        // Codes that aren't standard are kept, even if they aren't valid termcap.
+        assert_eq!(base.string("xx"), Some(&b"%%%q"[..]));
+        assert_eq!(base.number("Q9"), Some(3));
+        assert_eq!(base.string("kHLP"), Some(&b"\x1b?"[..]));
+        assert_eq!(base.string("acsc"), Some(&b"0a``%n"[..]));
+        // Fields starting with a period are commented out.
+        assert_eq!(base.string("sgr"), None);
+        assert_eq!(base.string("smglr"), Some(&b"\x1b[%i%p1%d;%p2%ds"[..]));
+        // Obsolete capabilities stand for standard ones.
+        assert_eq!(base.string("cub1"), Some(&b"\x08"[..]));
+        assert_eq!(base.string("ht"), Some(&b"\t"[..]));
+        assert_eq!(base.number("it"), Some(8));
+
+        let child = &entries[1];
+        assert_eq!(child.number("cols"), Some(132));
+        assert!(!child.bool("am"));
+        assert_eq!(child.string("cub1"), None);
+        assert_eq!(child.string("bel"), Some(&b"\x07"[..]));
+    }
+
+    #[test]
+    fn test_split_padding() {
+        assert_eq!(split_padding("5\\E[H"), ("5", "\\E[H"));
+        assert_eq!(split_padding("2.5*x"), ("2.5*", "x"));
+        assert_eq!(split_padding("\\E5"), ("", "\\E5"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = parse("t|test:am:\\\n\t:co#eighty:\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind), (2, 6, ErrorKind::InvalidNumber));
+        let e = parse("t|test:co=80:\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind), (1, 8, ErrorKind::WrongType("co".to_owned())));
+        let e = parse("t|test:tc=missing:\n").unwrap_err();
+        assert_eq!((e.line, e.column, e.kind),
+                   (1, 8, ErrorKind::UnknownUse("missing".to_owned())));
+        let e = parse("a|A:tc=b:\nb|B:tc=a:\n").unwrap_err();
+        assert_eq!((e.line, e.column), (2, 5));
+    }
+}
@@ -10,71 +10,461 @@
 
 //! ncurses-compatible database discovery
 //!
-//! Does not support hashed database, only filesystem!
+//! Each search location is either a directory tree or, if a `<location>.db` file exists, a
+//! Berkeley DB hashed database. `Searcher` lets the environment, home directory and default
+//! directories be supplied instead of read from the process.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 
-/// Return path to database entry for `term`
-pub fn get_dbpath_for_term(term: &str) -> Option<PathBuf> {
-    let mut dirs_to_search = Vec::new();
-    let first_char = match term.chars().next() {
-        Some(c) => c,
-        None => return None,
-    };
-
-    // Find search directory
-    match env::var_os("TERMINFO") {
-        Some(dir) => dirs_to_search.push(PathBuf::from(dir)),
-        None => {
-            if let Some(mut homedir) = env::home_dir() {
-                // ncurses compatibility;
-                homedir.push(".terminfo");
-                dirs_to_search.push(homedir)
+use Terminfo;
+#[cfg(feature = "builtin")]
+use builtin;
+use parser::compiled::TerminfoRef;
+use parser::hashed::HashedDatabase;
+
+/// The directories ncurses searches when neither `TERMINFO` nor `TERMINFO_DIRS` is set.
+///
+/// According to /etc/terminfo/README, after looking at ~/.terminfo, ncurses will search
+/// /etc/terminfo, then /lib/terminfo, and eventually /usr/share/terminfo.
+pub const DEFAULT_DIRS: &[&str] = &["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"];
+
+/// The hashed database for a search location: the location itself if it ends in `.db`, or the
+/// location with `.db` appended.
+fn hashed_path(dir: &Path) -> PathBuf {
+    if dir.extension().is_some_and(|ext| ext == "db") {
+        dir.to_path_buf()
+    } else {
+        let mut path = dir.to_path_buf().into_os_string();
+        path.push(".db");
+        PathBuf::from(path)
+    }
+}
+
+/// The result of looking up a terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookup {
+    /// The entry found, or the hashed database holding it.
+    pub path: Option<PathBuf>,
+    /// Every path checked, in order, including the one found.
+    pub checked: Vec<PathBuf>,
+    /// The entry, if it was read from a hashed database to find it.
+    entry: Option<Terminfo>,
+}
+
+/// Terminals that are better served by the entry of another one.
+static REPLACEMENTS: &[(&str, &str)] = &[("xterm-kitty", "xterm-256color")];
+
+/// The names to try for `term`, in order: `term` itself, then names with the last `-` suffix
+/// stripped (e.g. `-256color` or `-direct`), and finally `dumb`.
+///
+/// Some terminals are replaced by others along the way; `xterm-kitty` falls back to
+/// `xterm-256color`.
+///
+/// ```
+/// use terminfo::searcher::fallbacks;
+///
+/// assert_eq!(fallbacks("rxvt-unicode-256color"),
+///            ["rxvt-unicode-256color", "rxvt-unicode", "rxvt", "dumb"]);
+/// assert_eq!(fallbacks("xterm-kitty"), ["xterm-kitty", "xterm-256color", "xterm", "dumb"]);
+/// ```
+pub fn fallbacks(term: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut name = term;
+    while !name.is_empty() && !names.iter().any(|n| n == name) {
+        names.push(name.to_owned());
+        name = match REPLACEMENTS.iter().find(|&&(from, _)| from == name) {
+            Some(&(_, to)) => to,
+            None => name.rfind('-').map_or("", |i| &name[..i]),
+        };
+    }
+    if !names.iter().any(|n| n == "dumb") {
+        names.push("dumb".to_owned());
+    }
+    names
+}
+
+/// A terminal listed by `Searcher::entries`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The primary name, a valid `TERM` value.
+    pub name: String,
+    /// The other names of the terminal, also valid `TERM` values.
+    pub aliases: Vec<String>,
+    /// The last field of the names, if there are several.
+    pub description: Option<String>,
+    /// The compiled entry, or the hashed database holding it.
+    pub path: PathBuf,
+}
+
+impl Entry {
+    fn new(names: &str, path: PathBuf) -> Entry {
+        let mut names: Vec<String> = names.split('|').map(str::to_owned).collect();
+        let description = if names.len() > 1 { names.pop() } else { None };
+        let name = names.remove(0);
+        Entry {
+            name,
+            aliases: names,
+            description,
+            path,
+        }
+    }
+
+    /// Read the entry.
+    pub fn terminfo(&self) -> io::Result<Terminfo> {
+        read(&self.path, &self.name)
+    }
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "database not found")
+}
+
+/// Read the entry of `term` from `path`, a compiled entry or a hashed database.
+fn read(path: &Path, term: &str) -> io::Result<Terminfo> {
+    if HashedDatabase::is_hashed(path) {
+        HashedDatabase::open(path)?.entry(term)?.ok_or_else(not_found)
+    } else {
+        Terminfo::from_path(path)
+    }
+}
+
+/// The entries in a directory tree, by primary name.
+///
+/// Each name of an entry has a file of its own; the one named after the primary name is
+/// preferred. Files that can't be read are skipped.
+fn tree_entries(dir: &Path) -> BTreeMap<String, Entry> {
+    let mut entries = BTreeMap::new();
+    let files = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .flat_map(|subdir| fs::read_dir(subdir.path()).into_iter().flatten().flatten());
+    for file in files {
+        let path = file.path();
+        let buf = match fs::read(&path) {
+            Ok(buf) => buf,
+            Err(_) => continue,
+        };
+        let names = match TerminfoRef::parse(&buf) {
+            Ok(entry) => entry.names().collect::<Vec<_>>().join("|"),
+            Err(_) => continue,
+        };
+        let entry = Entry::new(&names, path);
+        let primary = entry.path.file_name().is_some_and(|name| *name == *entry.name);
+        if primary || !entries.contains_key(&entry.name) {
+            entries.insert(entry.name.clone(), entry);
+        }
+    }
+    entries
+}
+
+/// Every name of the terminals in some search locations, mapped to their entries.
+///
+/// A name refers to the first entry that has it, in search order.
+#[derive(Debug, Clone, Default)]
+pub struct AliasIndex {
+    entries: Vec<Entry>,
+    names: HashMap<String, usize>,
+}
+
+impl AliasIndex {
+    /// Scan the search locations of `searcher`.
+    pub fn new(searcher: &Searcher) -> AliasIndex {
+        let entries = searcher.scan();
+        let mut names = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            for name in Some(&entry.name).into_iter().chain(&entry.aliases) {
+                names.entry(name.clone()).or_insert(i);
             }
-            match env::var("TERMINFO_DIRS") {
-                Ok(dirs) => {
-                    for i in dirs.split(':') {
-                        if i == "" {
-                            dirs_to_search.push(PathBuf::from("/usr/share/terminfo"));
-                        } else {
-                            dirs_to_search.push(PathBuf::from(i));
-                        }
+        }
+        AliasIndex { entries, names }
+    }
+
+    /// Find the entry a name belongs to.
+    pub fn get(&self, name: &str) -> Option<&Entry> {
+        self.names.get(name).map(|&i| &self.entries[i])
+    }
+}
+
+/// The alias indexes built so far, by search locations.
+static ALIAS_INDEXES: OnceLock<Mutex<HashMap<Vec<PathBuf>, Arc<AliasIndex>>>> = OnceLock::new();
+
+/// Finds terminfo entries the way ncurses does, given an environment, a home directory and a
+/// list of default directories.
+///
+/// ```no_run
+/// use terminfo::searcher::Searcher;
+///
+/// let searcher = Searcher::new().var("TERMINFO", "/opt/terminfo");
+/// let lookup = searcher.lookup("xterm");
+/// if lookup.path.is_none() {
+///     println!("xterm not found, checked {:?}", lookup.checked);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Searcher {
+    env: HashMap<OsString, OsString>,
+    home_dir: Option<PathBuf>,
+    default_dirs: Vec<PathBuf>,
+}
+
+impl Default for Searcher {
+    fn default() -> Searcher {
+        Searcher::new()
+    }
+}
+
+impl Searcher {
+    /// A searcher with an empty environment, no home directory and `DEFAULT_DIRS`.
+    pub fn new() -> Searcher {
+        Searcher {
+            env: HashMap::new(),
+            home_dir: None,
+            default_dirs: DEFAULT_DIRS.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    /// A searcher using the environment and home directory of the process.
+    pub fn from_env() -> Searcher {
+        Searcher {
+            env: env::vars_os().collect(),
+            home_dir: env::home_dir(),
+            ..Searcher::new()
+        }
+    }
+
+    /// Replace the environment.
+    pub fn env<I, K, V>(mut self, vars: I) -> Searcher
+        where I: IntoIterator<Item = (K, V)>,
+              K: Into<OsString>,
+              V: Into<OsString>
+    {
+        self.env = vars.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        self
+    }
+
+    /// Set one environment variable.
+    pub fn var<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Searcher {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the home directory, whose `.terminfo` is searched first unless `TERMINFO` is set.
+    pub fn home_dir<P: Into<PathBuf>>(mut self, dir: P) -> Searcher {
+        self.home_dir = Some(dir.into());
+        self
+    }
+
+    /// Replace the directories searched when `TERMINFO_DIRS` is not set.
+    ///
+    /// Empty entries in `TERMINFO_DIRS` also stand for these.
+    pub fn default_dirs<I, P>(mut self, dirs: I) -> Searcher
+        where I: IntoIterator<Item = P>,
+              P: Into<PathBuf>
+    {
+        self.default_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The search locations, in order.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        let mut dirs_to_search = Vec::new();
+
+        if let Some(dir) = self.env.get(OsStr::new("TERMINFO")) {
+            dirs_to_search.push(PathBuf::from(dir));
+            return dirs_to_search;
+        }
+
+        if let Some(ref homedir) = self.home_dir {
+            // ncurses compatibility;
+            dirs_to_search.push(homedir.join(".terminfo"));
+        }
+        match self.env.get(OsStr::new("TERMINFO_DIRS")).and_then(|dirs| dirs.to_str()) {
+            Some(dirs) => {
+                for i in dirs.split(':') {
+                    if i.is_empty() {
+                        dirs_to_search.extend(self.default_dirs.iter().cloned());
+                    } else {
+                        dirs_to_search.push(PathBuf::from(i));
                     }
                 }
-                // Found nothing in TERMINFO_DIRS, use the default paths:
-                // According to  /etc/terminfo/README, after looking at
-                // ~/.terminfo, ncurses will search /etc/terminfo, then
-                // /lib/terminfo, and eventually /usr/share/terminfo.
-                Err(..) => {
-                    dirs_to_search.push(PathBuf::from("/etc/terminfo"));
-                    dirs_to_search.push(PathBuf::from("/lib/terminfo"));
-                    dirs_to_search.push(PathBuf::from("/usr/share/terminfo"));
-                }
             }
+            None => dirs_to_search.extend(self.default_dirs.iter().cloned()),
         }
-    };
-
-    // Look for the terminal in all of the search directories
-    for mut p in dirs_to_search {
-        if fs::metadata(&p).is_ok() {
-            p.push(&first_char.to_string());
-            p.push(&term);
-            if fs::metadata(&p).is_ok() {
-                return Some(p);
+        dirs_to_search
+    }
+
+    /// Look for the entry of `term` in all of the search locations.
+    pub fn lookup(&self, term: &str) -> Lookup {
+        let mut checked = Vec::new();
+        let first_char = match term.chars().next() {
+            Some(c) => c,
+            None => {
+                return Lookup {
+                    path: None,
+                    checked,
+                    entry: None,
+                }
+            }
+        };
+
+        for dir in self.dirs() {
+            let db = hashed_path(&dir);
+            checked.push(db.clone());
+            if db.is_file() && HashedDatabase::is_hashed(&db) {
+                // A hashed database hides the directory tree of its location. The entry is kept
+                // so that the database isn't read again.
+                match HashedDatabase::open(&db).and_then(|hashed| hashed.entry(term)) {
+                    Ok(Some(info)) => {
+                        return Lookup {
+                            path: Some(db),
+                            checked,
+                            entry: Some(info),
+                        }
+                    }
+                    _ => continue,
+                }
             }
-            p.pop();
-            p.pop();
 
             // on some installations the dir is named after the hex of the char
             // (e.g. OS X)
-            p.push(&format!("{:x}", first_char as usize));
-            p.push(term);
-            if fs::metadata(&p).is_ok() {
-                return Some(p);
+            for subdir in &[first_char.to_string(), format!("{:x}", first_char as usize)] {
+                let path = dir.join(subdir).join(term);
+                checked.push(path.clone());
+                if fs::metadata(&path).is_ok() {
+                    return Lookup {
+                        path: Some(path),
+                        checked,
+                        entry: None,
+                    };
+                }
+            }
+        }
+        Lookup {
+            path: None,
+            checked,
+            entry: None,
+        }
+    }
+
+    /// List every terminal in the search locations, sorted by name, like `toe`.
+    ///
+    /// An entry hides the ones with the same primary name in later locations. Unreadable
+    /// locations and entries are skipped.
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut entries = self.scan();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    /// The entries of the search locations in search order, without the hidden ones.
+    fn scan(&self) -> Vec<Entry> {
+        let mut names = HashSet::new();
+        let mut entries = Vec::new();
+        for dir in self.dirs() {
+            let db = hashed_path(&dir);
+            let found = if db.is_file() && HashedDatabase::is_hashed(&db) {
+                let names = HashedDatabase::open(&db).and_then(|db| db.names()).unwrap_or_default();
+                names.iter()
+                     .map(|names| Entry::new(names, db.clone()))
+                     .map(|entry| (entry.name.clone(), entry))
+                     .collect()
+            } else {
+                tree_entries(&dir)
+            };
+            entries.extend(found.into_values().filter(|entry| names.insert(entry.name.clone())));
+        }
+        entries
+    }
+
+    /// The alias index of the search locations, for finding terminals by names that have no file
+    /// (or hashed database record) of their own.
+    ///
+    /// Building it reads every entry, so lookups don't use it unless asked to. It is built on
+    /// first use and shared by all searchers with the same search locations for the lifetime of
+    /// the process. Use `AliasIndex::new` for an up-to-date one.
+    pub fn aliases(&self) -> Arc<AliasIndex> {
+        let cache = ALIAS_INDEXES.get_or_init(Default::default);
+        let dirs = self.dirs();
+        if let Some(index) = cache.lock().unwrap_or_else(PoisonError::into_inner).get(&dirs) {
+            return index.clone();
+        }
+        // Scan without holding the lock; if another thread got there first, use its index.
+        let index = Arc::new(AliasIndex::new(self));
+        cache.lock().unwrap_or_else(PoisonError::into_inner).entry(dirs).or_insert(index).clone()
+    }
+
+    /// Return path to database entry for `term`
+    ///
+    /// For entries in a hashed database, this is the path to the database file.
+    pub fn find(&self, term: &str) -> Option<PathBuf> {
+        self.lookup(term).path
+    }
+
+    /// Read the entry of `term`.
+    ///
+    /// Only the paths listed by `lookup` are read. `tic` gives every name of a terminal a file
+    /// (or hashed database record), so this finds it by any of them; names that have none are
+    /// found with `aliases`. With the `builtin` feature, terminals not found at all are looked up
+    /// among the entries compiled into the crate.
+    pub fn entry(&self, term: &str) -> io::Result<Terminfo> {
+        let lookup = self.lookup(term);
+        let result = match (lookup.entry, lookup.path) {
+            (Some(info), _) => Ok(info),
+            (None, Some(path)) => Terminfo::from_path(path),
+            (None, None) => Err(not_found()),
+        };
+        #[cfg(feature = "builtin")]
+        let result = result.or_else(|err| match err.kind() {
+            io::ErrorKind::NotFound => builtin::entry(term).ok_or(err),
+            _ => Err(err),
+        });
+        result
+    }
+
+    /// Read the entry of the terminal named by `TERM`, or of the first of its `fallbacks` found.
+    ///
+    /// Returns the name that was found along with the entry. If none is, the error is the one
+    /// for `TERM` itself.
+    pub fn term_entry(&self) -> io::Result<(String, Terminfo)> {
+        let term = self.env.get(OsStr::new("TERM")).and_then(|term| term.to_str()).unwrap_or("");
+        let mut first_error = None;
+        for name in fallbacks(term) {
+            match self.entry(&name) {
+                Ok(info) => return Ok((name, info)),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
             }
         }
+        Err(first_error.unwrap_or_else(not_found))
+    }
+}
+
+/// Return path to database entry for `term`
+///
+/// For entries in a hashed database, this is the path to the database file.
+pub fn get_dbpath_for_term(term: &str) -> Option<PathBuf> {
+    Searcher::from_env().find(term)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Searcher, ALIAS_INDEXES};
+
+    #[test]
+    fn test_miss_does_not_scan() {
+        let searcher = Searcher::new().default_dirs(vec!["tests/tree", "tests/hashed/terminfo"]);
+        // vt200 is only listed in the entry of vt220.
+        assert!(searcher.entry("vt200").is_err());
+        assert!(searcher.clone().var("TERM", "nonexistent").term_entry().is_ok());
+        let indexes = ALIAS_INDEXES.get_or_init(Default::default).lock().unwrap();
+        assert!(!indexes.contains_key(&searcher.dirs()));
     }
-    None
 }
@@ -0,0 +1,232 @@
+//! ncurses-compatible compiled terminfo format writing (term(5))
+//!
+//! This is the inverse of `parser::compiled::parse`.
+
+use std::io;
+
+use {StringValue, Terminfo};
+
+/// The largest section or string table size the header can describe.
+const MAX_SIZE: usize = 0x7FFF;
+
+/// How numbers are stored in a compiled entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// 16-bit numbers (magic `0x11A`), understood by every ncurses version. Values must not
+    /// exceed 32767.
+    Legacy,
+    /// 32-bit numbers (magic `0x21E`), understood by ncurses 6.1 and later.
+    Wide,
+}
+
+impl NumberFormat {
+    fn magic(self) -> u16 {
+        match self {
+            NumberFormat::Legacy => 0x011A,
+            NumberFormat::Wide => 0x021E,
+        }
+    }
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn push_le_u16(buf: &mut Vec<u8>, n: u16) {
+    buf.extend_from_slice(&n.to_le_bytes());
+}
+
+fn push_size(buf: &mut Vec<u8>, n: usize) -> io::Result<()> {
+    if n > MAX_SIZE {
+        return Err(invalid("section too large for the compiled format"));
+    }
+    push_le_u16(buf, n as u16);
+    Ok(())
+}
+
+fn push_number(buf: &mut Vec<u8>, n: Option<u32>, format: NumberFormat) -> io::Result<()> {
+    match (format, n) {
+        (NumberFormat::Legacy, None) => push_le_u16(buf, 0xFFFF),
+        (NumberFormat::Legacy, Some(n)) if n <= 0x7FFF => push_le_u16(buf, n as u16),
+        (NumberFormat::Legacy, Some(_)) => {
+            return Err(invalid("number too large for the legacy compiled format"))
+        }
+        (NumberFormat::Wide, None) => buf.extend_from_slice(&(-1i32).to_le_bytes()),
+        (NumberFormat::Wide, Some(n)) if n <= i32::MAX as u32 => {
+            buf.extend_from_slice(&n.to_le_bytes())
+        }
+        (NumberFormat::Wide, Some(_)) => return Err(invalid("number too large for 32 bits")),
+    }
+    Ok(())
+}
+
+fn pad_to_even(buf: &mut Vec<u8>) {
+    if buf.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
+
+/// Appends string values to a string table and records their offsets.
+#[derive(Default)]
+struct StringTable {
+    offsets: Vec<u16>,
+    table: Vec<u8>,
+}
+
+impl StringTable {
+    fn push(&mut self, value: Option<&[u8]>) -> io::Result<()> {
+        let offset = match value {
+            None => 0xFFFF,
+            Some(s) => {
+                let offset = self.table.len();
+                if offset > MAX_SIZE {
+                    return Err(invalid("string table too large for the compiled format"));
+                }
+                self.table.extend_from_slice(s);
+                self.table.push(0);
+                offset as u16
+            }
+        };
+        self.offsets.push(offset);
+        Ok(())
+    }
+
+    /// Append a string capability of `info`, writing cancelled ones as `0xFFFE`.
+    fn push_value(&mut self, info: &Terminfo, value: Option<StringValue>) -> io::Result<()> {
+        match value {
+            Some(StringValue::Cancelled) => {
+                self.offsets.push(0xFFFE);
+                Ok(())
+            }
+            value => self.push(value.and_then(|value| info.value(value))),
+        }
+    }
+}
+
+/// The length of the shortest prefix of `values` that covers every value present.
+fn count<T>(values: &[Option<T>]) -> usize {
+    values.iter().rposition(Option::is_some).map_or(0, |i| i + 1)
+}
+
+/// The standard values of a type, with the unknown ones at their own indices.
+fn with_unknown<T: Copy>(known: &[Option<T>], unknown: &[(usize, T)]) -> Vec<Option<T>> {
+    let mut values = known.to_vec();
+    for &(i, value) in unknown {
+        if values.len() <= i {
+            values.resize(i + 1, None);
+        }
+        values[i] = Some(value);
+    }
+    values
+}
+
+/// Write a compiled terminfo entry, choosing the legacy number format unless some number is too
+/// large for it.
+pub fn write(info: &Terminfo, file: &mut dyn io::Write) -> io::Result<()> {
+    let mut numbers = info.numbers().map(|(_, n)| n).chain(info.unknown_numbers().map(|(_, n)| n));
+    let format = if numbers.any(|n| n > 0x7FFF) {
+        NumberFormat::Wide
+    } else {
+        NumberFormat::Legacy
+    };
+    write_with_format(info, file, format)
+}
+
+/// Write a compiled terminfo entry using the given number format.
+pub fn write_with_format(info: &Terminfo,
+                         file: &mut dyn io::Write,
+                         format: NumberFormat)
+                         -> io::Result<()> {
+    let names = info.names.join("|");
+    if names.is_empty() {
+        return Err(invalid("no names exposed, need at least one"));
+    }
+    let names_bytes = names.len() + 1;
+
+    // Unknown standard capabilities go back to their own slots.
+    let known = 64 - info.bools.leading_zeros();
+    let mut bools: Vec<bool> = (0..known).map(|i| info.bools >> i & 1 == 1).collect();
+    for &i in &info.unknown_bools {
+        if bools.len() <= i {
+            bools.resize(i + 1, false);
+        }
+        bools[i] = true;
+    }
+    let numbers = with_unknown(&info.numbers, &info.unknown_numbers);
+    let values = with_unknown(&info.strings, &info.unknown_strings);
+    let numbers_count = count(&numbers);
+    let strings_count = count(&values);
+
+    let mut strings = StringTable::default();
+    for &value in &values[..strings_count] {
+        strings.push_value(info, value)?;
+    }
+
+    let mut buf = Vec::new();
+    push_le_u16(&mut buf, format.magic());
+    push_size(&mut buf, names_bytes)?;
+    push_size(&mut buf, bools.len())?;
+    push_size(&mut buf, numbers_count)?;
+    push_size(&mut buf, strings_count)?;
+    push_size(&mut buf, strings.table.len())?;
+
+    buf.extend_from_slice(names.as_bytes());
+    buf.push(0);
+
+    buf.extend(bools.iter().map(|&b| b as u8));
+    pad_to_even(&mut buf);
+
+    for &n in &numbers[..numbers_count] {
+        push_number(&mut buf, n, format)?;
+    }
+
+    for &offset in &strings.offsets {
+        push_le_u16(&mut buf, offset);
+    }
+    buf.extend_from_slice(&strings.table);
+
+    // Extended capabilities are kept sorted by name, as ncurses expects.
+    let ext_bools = &info.ext_bools;
+    let ext_numbers = &info.ext_numbers;
+    let ext_strings = &info.ext_strings;
+
+    if !ext_bools.is_empty() || !ext_numbers.is_empty() || !ext_strings.is_empty() {
+        pad_to_even(&mut buf);
+
+        let mut values = StringTable::default();
+        for &(_, value) in ext_strings {
+            values.push_value(info, Some(value))?;
+        }
+
+        // Name offsets are relative to the end of the string values.
+        let mut names = StringTable::default();
+        let ext_names = ext_bools.iter()
+                                 .chain(ext_numbers.iter().map(|entry| &entry.0))
+                                 .chain(ext_strings.iter().map(|entry| &entry.0));
+        for name in ext_names {
+            names.push(Some(name.as_bytes()))?;
+        }
+
+        let present = values.offsets.iter().filter(|&&offset| offset < 0xFFFE).count();
+
+        push_size(&mut buf, ext_bools.len())?;
+        push_size(&mut buf, ext_numbers.len())?;
+        push_size(&mut buf, ext_strings.len())?;
+        push_size(&mut buf, present + names.offsets.len())?;
+        push_size(&mut buf, values.table.len() + names.table.len())?;
+
+        buf.extend(ext_bools.iter().map(|_| 1));
+        pad_to_even(&mut buf);
+
+        for &(_, n) in ext_numbers {
+            push_number(&mut buf, Some(n), format)?;
+        }
+        for &offset in values.offsets.iter().chain(&names.offsets) {
+            push_le_u16(&mut buf, offset);
+        }
+        buf.extend_from_slice(&values.table);
+        buf.extend_from_slice(&names.table);
+    }
+
+    file.write_all(&buf)
+}
@@ -0,0 +1,144 @@
+//! Terminfo source format writing, in the style of `infocmp`
+//!
+//! The output can be read back with `parser::source::parse` (or `tic`).
+
+use std::io;
+
+use Terminfo;
+
+/// The line width `infocmp` wraps capabilities at by default.
+const WIDTH: usize = 60;
+
+/// The width `infocmp` counts for the tab each capability line starts with.
+const INDENT: usize = 1;
+
+/// Format a number like `infocmp`: in hexadecimal if it is close to a power of two.
+fn format_number(n: u32) -> String {
+    let near_power_of_two = (8..32).any(|bits| {
+        let power = 1u64 << bits;
+        power - 16 <= n as u64 && (n as u64) < power + 16
+    });
+    if n > 255 && near_power_of_two {
+        format!("{:#x}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Escape a string value so that it can be read back by `parser::source::parse`.
+fn escape(s: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for (i, &b) in s.iter().enumerate() {
+        let after_percent = i > 0 && s[i - 1] == b'%';
+        let next = s.get(i + 1).cloned();
+        match b {
+            b'\x1b' => out.push_str("\\E"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\\' => out.push_str("\\\\"),
+            b',' => out.push_str("\\,"),
+            // "%^" is the XOR operator, not an escaped control character.
+            b'^' if after_percent => out.push('^'),
+            b'^' => out.push_str("\\^"),
+            // Leading and trailing spaces would be lost.
+            b' ' if i == 0 || s[i..].iter().all(|&c| c == b' ') => out.push_str("\\s"),
+            0x20..=0x7e => out.push(b as char),
+            0x7f if !after_percent => out.push_str("^?"),
+            0x00..=0x1f if !after_percent => {
+                out.push('^');
+                out.push((b + b'@') as char);
+            }
+            // NUL can't appear in a compiled string; \0 stands for \200 instead.
+            0x80 if !next.is_some_and(|c| c.is_ascii_digit()) => out.push_str("\\0"),
+            _ => out.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    out
+}
+
+/// Packs comma-terminated fields into indented lines.
+struct Lines<'a> {
+    out: &'a mut dyn io::Write,
+    len: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn field(&mut self, field: &str) -> io::Result<()> {
+        if self.len > 0 && self.len + 1 + field.len() + 1 > WIDTH {
+            self.end()?;
+        }
+        if self.len == 0 {
+            self.out.write_all(b"\t")?;
+            self.len = INDENT;
+        } else {
+            self.out.write_all(b" ")?;
+            self.len += 1;
+        }
+        write!(self.out, "{},", field)?;
+        self.len += field.len() + 1;
+        Ok(())
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        if self.len > 0 {
+            self.out.write_all(b"\n")?;
+            self.len = 0;
+        }
+        Ok(())
+    }
+}
+
+/// Write an entry in terminfo source format.
+///
+/// The names come first, followed by the booleans, numbers and strings, each starting on a new
+/// line. Standard capabilities are listed in their `boolnames`/`numnames`/`stringnames` order,
+/// followed by extended capabilities sorted by name. Cancelled strings are written as `name@`.
+/// Standard capabilities unknown to this crate have no name to be written under, and are left
+/// out.
+pub fn write(info: &Terminfo, out: &mut dyn io::Write) -> io::Result<()> {
+    writeln!(out, "{},", info.names.join("|"))?;
+
+    let mut lines = Lines { out, len: 0 };
+
+    for name in info.bools() {
+        lines.field(name)?;
+    }
+    lines.end()?;
+
+    for (name, n) in info.numbers() {
+        lines.field(&format!("{}#{}", name, format_number(n)))?;
+    }
+    lines.end()?;
+
+    for (name, value) in info.string_values() {
+        match info.value(value) {
+            Some(s) => lines.field(&format!("{}={}", name, escape(s)))?,
+            None => lines.field(&format!("{}@", name))?,
+        }
+    }
+    lines.end()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, format_number};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(b"\x1b[%i%p1%d;%p2%dH"), "\\E[%i%p1%d;%p2%dH");
+        assert_eq!(escape(b"\x08\x7f\r\n"), "^H^?\\r\\n");
+        assert_eq!(escape(b"a,b\\c^d%^"), "a\\,b\\\\c\\^d%^");
+        assert_eq!(escape(b" x "), "\\sx\\s");
+        assert_eq!(escape(b"\x80\x801\xff"), "\\0\\2001\\377");
+        assert_eq!(escape(b"%\x01"), "%\\001");
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(80), "80");
+        assert_eq!(format_number(256), "0x100");
+        assert_eq!(format_number(1000), "1000");
+        assert_eq!(format_number(0x1000000), "0x1000000");
+        assert_eq!(format_number(32767), "0x7fff");
+    }
+}
@@ -1,6 +1,8 @@
 extern crate terminfo;
 
 use terminfo::Terminfo;
+use terminfo::parser::compiled::parse;
+use terminfo::writer::compiled::{self, NumberFormat};
 use std::fs;
 
 #[test]
@@ -9,3 +11,710 @@ fn test_parse() {
         let _ = Terminfo::from_path(f.unwrap().path()).unwrap();
     }
 }
+
+#[test]
+fn test_parse_leaves_trailing_data() {
+    let xterm = fs::read("tests/data/xterm-direct").unwrap();
+    let linux = fs::read("tests/data/linux").unwrap();
+    let dumb = fs::read("tests/data/dumb").unwrap();
+
+    // Entries with an extended section can be followed by anything.
+    let mut stream = [&xterm[..], &linux[..], b"trailing"].concat();
+    let mut r = &stream[..];
+    assert_eq!(parse(&mut r).unwrap(), Terminfo::from_path("tests/data/xterm-direct").unwrap());
+    assert_eq!(parse(&mut r).unwrap(), Terminfo::from_path("tests/data/linux").unwrap());
+    assert_eq!(r, b"trailing");
+
+    // One without must end the stream.
+    stream = [&linux[..], &dumb[..]].concat();
+    let mut r = &stream[..];
+    assert_eq!(parse(&mut r).unwrap(), Terminfo::from_path("tests/data/linux").unwrap());
+    assert_eq!(parse(&mut r).unwrap(), Terminfo::from_path("tests/data/dumb").unwrap());
+    assert!(r.is_empty());
+}
+
+#[test]
+fn test_32bit_numbers() {
+    let info = Terminfo::from_path("tests/data/xterm-direct").unwrap();
+    assert_eq!(info.number("colors"), Some(0x1000000));
+    assert_eq!(info.number("pairs"), Some(0x10000));
+    assert_eq!(info.number("cols"), Some(80));
+}
+
+#[test]
+fn test_unknown_standard_capabilities() {
+    use terminfo::TerminfoRef;
+    use terminfo::parser::compiled::{boolnames, numnames, stringnames};
+
+    // An entry from a newer ncurses, with one more standard capability of each type.
+    let bools = boolnames.len() + 1;
+    let numbers = numnames.len() + 1;
+    let strings = stringnames.len() + 1;
+    let names = b"new|newer terminal\0";
+    let table = b"\x1b[?1u\0";
+    let mut buf = Vec::new();
+    for n in [0x11a, names.len(), bools, numbers, strings, table.len()] {
+        buf.extend_from_slice(&(n as u16).to_le_bytes());
+    }
+    buf.extend_from_slice(names);
+    buf.extend((0..bools).map(|i| (i == 1 || i == bools - 1) as u8));
+    if (names.len() + bools) % 2 == 1 {
+        buf.push(0);
+    }
+    for i in 0..numbers {
+        let n: u16 = if i == numbers - 1 { 7 } else { 0xffff };
+        buf.extend_from_slice(&n.to_le_bytes());
+    }
+    for i in 0..strings {
+        let offset: u16 = if i == strings - 1 { 0 } else { 0xffff };
+        buf.extend_from_slice(&offset.to_le_bytes());
+    }
+    buf.extend_from_slice(table);
+
+    let bool_name = format!("bool{}", bools - 1);
+    let number_name = format!("num{}", numbers - 1);
+    let string_name = format!("str{}", strings - 1);
+    let entry = TerminfoRef::parse(&buf).unwrap();
+    assert!(entry.bool("am"));
+    assert!(entry.bool(&bool_name));
+    assert_eq!(entry.bools().collect::<Vec<_>>(), ["am"]);
+    assert_eq!(entry.unknown_bools().collect::<Vec<_>>(), [bools - 1]);
+    assert_eq!(entry.numbers().count(), 0);
+    assert_eq!(entry.unknown_numbers().collect::<Vec<_>>(), [(numbers - 1, 7)]);
+    assert_eq!(entry.string(&string_name), Some(&b"\x1b[?1u"[..]));
+    assert_eq!(entry.number(&format!("num0{}", numbers - 1)), None);
+
+    let info = entry.to_terminfo();
+    assert!(info.bool("am"));
+    assert!(info.bool(&bool_name));
+    assert_eq!(info.number(&number_name), Some(7));
+    assert_eq!(info.string(&string_name), Some(&b"\x1b[?1u"[..]));
+    assert!(entry.strings().eq(info.strings()));
+    assert!(entry.unknown_bools().eq(info.unknown_bools()));
+    assert!(entry.unknown_numbers().eq(info.unknown_numbers()));
+    assert!(entry.unknown_strings().eq(info.unknown_strings()));
+
+    // They are written back to their standard slots, not as extended capabilities.
+    let mut written = Vec::new();
+    compiled::write(&info, &mut written).unwrap();
+    assert_eq!(written, buf);
+    assert_eq!(parse(&mut &written[..]).unwrap(), info);
+}
+
+#[test]
+fn test_extended() {
+    let info = Terminfo::from_path("tests/data/xterm-direct").unwrap();
+    assert!(info.bool("RGB"));
+    assert!(info.bool("XT"));
+    assert_eq!(info.number("CO"), Some(8));
+    assert_eq!(info.string("Ss"), Some(&b"\x1b[%p1%d q"[..]));
+    assert_eq!(info.string("XM"),
+               Some(&b"\x1b[?1006;1000%?%p1%{1}%=%th%el%;"[..]));
+    // Standard capabilities are still there.
+    assert_eq!(info.string("setaf"),
+               Some(&b"\x1b[%?%p1%{8}%<%t3%p1%d%e38:2::%p1%{65536}%/%d:%p1%{256}%/%{255}%&%d:%p1%{255}%&%d%;m"[..]));
+
+    let info = Terminfo::from_path("tests/data/linux").unwrap();
+    assert!(info.bool("AX"));
+    assert_eq!(info.number("U8"), Some(1));
+}
+
+#[test]
+fn test_write_roundtrip() {
+    for f in fs::read_dir("tests/data/").unwrap() {
+        let path = f.unwrap().path();
+        let info = Terminfo::from_path(&path).unwrap();
+        let mut buf = Vec::new();
+        compiled::write(&info, &mut buf).unwrap();
+        let reparsed = parse(&mut &buf[..]).unwrap();
+        assert_eq!(info, reparsed, "{} changed after a round trip", path.display());
+    }
+}
+
+#[test]
+fn test_write_legacy_overflow() {
+    let info = Terminfo::from_path("tests/data/xterm-direct").unwrap();
+    let mut buf = Vec::new();
+    assert!(compiled::write_with_format(&info, &mut buf, NumberFormat::Legacy).is_err());
+    buf.clear();
+    compiled::write_with_format(&info, &mut buf, NumberFormat::Wide).unwrap();
+    assert_eq!(&buf[..2], &[0x1e, 0x02]);
+}
+
+#[test]
+fn test_standard_name_order() {
+    // A legacy entry that only sets boolean 12, number 33 and string 397, which ncurses calls
+    // db, OTug and OTbc.
+    let mut buf = Vec::new();
+    for &n in &[0x11a_u16, 2, 13, 34, 398, 2] {
+        buf.extend_from_slice(&n.to_le_bytes());
+    }
+    buf.extend_from_slice(b"t\0");
+    buf.extend_from_slice(&[0; 12]);
+    buf.extend_from_slice(&[1, 0]);
+    for _ in 0..33 {
+        buf.extend_from_slice(&[0xff, 0xff]);
+    }
+    buf.extend_from_slice(&[1, 0]);
+    for _ in 0..397 {
+        buf.extend_from_slice(&[0xff, 0xff]);
+    }
+    buf.extend_from_slice(&[0, 0]);
+    buf.extend_from_slice(b"\x08\0");
+
+    let info = parse(&mut &buf[..]).unwrap();
+    assert!(info.bool("db"));
+    assert!(!info.bool("da"));
+    assert_eq!(info.number("OTug"), Some(1));
+    assert_eq!(info.string("OTbc"), Some(&b"\x08"[..]));
+}
+
+#[test]
+fn test_parse_source() {
+    let source = fs::read_to_string("tests/source/xterm-direct.ti").unwrap();
+    let entries = terminfo::parser::source::parse(&source).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0], Terminfo::from_path("tests/data/xterm-direct").unwrap());
+}
+
+#[test]
+fn test_write_source_roundtrip() {
+    for f in fs::read_dir("tests/data/").unwrap() {
+        let path = f.unwrap().path();
+        let mut info = Terminfo::from_path(&path).unwrap();
+        let mut source = Vec::new();
+        terminfo::writer::source::write(&info, &mut source).unwrap();
+        let source = String::from_utf8(source).unwrap();
+        let entries = terminfo::parser::source::parse(&source).unwrap();
+        // Cancelled capabilities are dropped when reading source.
+        let cancelled: Vec<String> = info.cancelled_strings().map(|name| name.to_owned()).collect();
+        for name in cancelled {
+            info.set_string(&name, None);
+        }
+        assert_eq!(entries, [info], "{}", path.display());
+    }
+}
+
+#[test]
+fn test_typed_capabilities() {
+    use terminfo::{BoolCap, NumberCap, StringCap};
+
+    let info = Terminfo::from_path("tests/data/xterm-direct").unwrap();
+    assert!(info.get(BoolCap::AutoRightMargin));
+    assert!(!info.contains(BoolCap::HardCopy));
+    assert_eq!(info.get(NumberCap::MaxColors), Some(0x1000000));
+    assert_eq!(info.get(StringCap::SetAForeground), info.string("setaf"));
+    assert!(info.contains(StringCap::CursorAddress));
+    assert_eq!(info.get(StringCap::ZeroMotion), None);
+    assert!(info.bool("RGB"));
+
+    assert_eq!(StringCap::SetAForeground.name(), "setaf");
+    assert_eq!(StringCap::from_name("setaf"), Some(StringCap::SetAForeground));
+    assert_eq!(StringCap::from_name("setaff"), None);
+    assert_eq!(StringCap::ALL.len(), terminfo::parser::compiled::stringnames.len());
+}
+
+#[test]
+fn test_build_entry() {
+    use terminfo::{BoolCap, NumberCap, StringCap};
+
+    let mut info = Terminfo::new(vec!["test".to_owned(), "Test terminal".to_owned()]);
+    info.set_bool("am", true);
+    info.set_bool("Tc", true);
+    info.set_number("cols", Some(80));
+    info.set_number("U8", Some(1));
+    info.set_string("bel", Some(b"\x07"));
+    info.set_string("Ss", Some(b"\x1b[%p1%d q"));
+    info.set_string("el", Some(b""));
+    info.set_string("bel", Some(b"\x07\x07"));
+    info.cancel_string("ind");
+    info.cancel_string("XT");
+
+    assert!(info.get(BoolCap::AutoRightMargin));
+    assert_eq!(info.get(NumberCap::Columns), Some(80));
+    assert_eq!(info.get(StringCap::Bell), Some(&b"\x07\x07"[..]));
+    assert_eq!(info.get(StringCap::ClrEol), Some(&b""[..]));
+    assert_eq!(info.string("el"), Some(&b""[..]));
+    assert_eq!(info.get(StringCap::ScrollForward), None);
+    assert_eq!(info.string("XT"), None);
+    assert_eq!(info.cancelled_strings().collect::<Vec<_>>(), ["ind", "XT"]);
+    assert_eq!(info.bools().collect::<Vec<_>>(), ["am", "Tc"]);
+    assert_eq!(info.numbers().collect::<Vec<_>>(), [("cols", 80), ("U8", 1)]);
+
+    // Empty and cancelled strings survive being written.
+    let mut buf = Vec::new();
+    compiled::write(&info, &mut buf).unwrap();
+    let parsed = parse(&mut &buf[..]).unwrap();
+    assert_eq!(parsed, info);
+    assert_eq!(parsed.get(StringCap::ClrEol), Some(&b""[..]));
+    assert_eq!(parsed.cancelled_strings().collect::<Vec<_>>(), ["ind", "XT"]);
+    let mut rewritten = Vec::new();
+    compiled::write(&parsed, &mut rewritten).unwrap();
+    assert_eq!(rewritten, buf);
+
+    info.set_bool("Tc", false);
+    info.set_number("cols", None);
+    info.set_string("Ss", None);
+    assert!(!info.bool("Tc"));
+    assert_eq!(info.number("cols"), None);
+    assert_eq!(info.string("Ss"), None);
+}
+
+#[test]
+fn test_terminfo_ref() {
+    use terminfo::TerminfoRef;
+
+    for f in fs::read_dir("tests/data/").unwrap() {
+        let path = f.unwrap().path();
+        let buf = fs::read(&path).unwrap();
+        let entry = TerminfoRef::parse(&buf).unwrap();
+        let info = Terminfo::from_path(&path).unwrap();
+        assert_eq!(entry.names().collect::<Vec<_>>(), info.names);
+        assert!(entry.bools().eq(info.bools()));
+        assert!(entry.numbers().eq(info.numbers()));
+        assert!(entry.strings().eq(info.strings()));
+        assert!(entry.cancelled_strings().eq(info.cancelled_strings()));
+        assert_eq!(entry.to_terminfo(), info);
+    }
+
+    static XTERM_DIRECT: &[u8] = include_bytes!("data/xterm-direct");
+    let entry = TerminfoRef::parse(XTERM_DIRECT).unwrap();
+    assert!(entry.bool("am"));
+    assert!(entry.bool("RGB"));
+    assert_eq!(entry.number("colors"), Some(0x1000000));
+    assert_eq!(entry.number("CO"), Some(8));
+    let setaf = entry.string("setaf").unwrap();
+    assert!(XTERM_DIRECT.as_ptr_range().contains(&setaf.as_ptr()));
+    assert_eq!(entry.string("Ss"), Some(&b"\x1b[%p1%d q"[..]));
+    assert_eq!(entry.string("zerom"), None);
+
+    assert!(TerminfoRef::parse(&XTERM_DIRECT[..100]).is_err());
+}
+
+#[test]
+fn test_hashed_database() {
+    use terminfo::parser::hashed::HashedDatabase;
+
+    for path in &["tests/hashed/terminfo.db", "tests/hashed/terminfo-be.db"] {
+        assert!(HashedDatabase::is_hashed(path));
+        let db = HashedDatabase::open(path).unwrap();
+        for name in &["dumb", "linux", "screen", "xterm-256color", "xterm-direct"] {
+            let info = Terminfo::from_path(format!("tests/data/{}", name)).unwrap();
+            assert_eq!(db.entry(name).unwrap().as_ref(), Some(&info));
+        }
+        // Aliases refer to the record stored under the full names field.
+        let xterm = db.get(b"xterm-256color").unwrap().unwrap();
+        assert_eq!(xterm, b"\x02xterm-256color|xterm with 256 colors");
+        let entry = db.get(b"xterm-256color|xterm with 256 colors").unwrap().unwrap();
+        assert!(entry.starts_with(b"\0"));
+        assert_eq!(db.entry("nonexistent").unwrap(), None);
+    }
+
+    assert!(!HashedDatabase::is_hashed("tests/data/xterm"));
+    assert!(HashedDatabase::open("tests/data/xterm").is_err());
+}
+
+#[test]
+fn test_hashed_database_corrupt_header() {
+    use std::io;
+    use terminfo::parser::hashed::HashedDatabase;
+
+    let data = fs::read("tests/hashed/terminfo.db").unwrap();
+    // The header fields are big-endian: max_bucket, high_mask and low_mask are fields 10 to 12.
+    let corrupt = |field: usize, value: u32| {
+        let mut data = data.clone();
+        data[4 * field..4 * field + 4].copy_from_slice(&value.to_be_bytes());
+        HashedDatabase::parse(data).unwrap_err().kind()
+    };
+    assert_eq!(corrupt(10, u32::MAX), io::ErrorKind::InvalidData);
+    assert_eq!(corrupt(11, u32::MAX), io::ErrorKind::InvalidData);
+    assert_eq!(corrupt(12, u32::MAX), io::ErrorKind::InvalidData);
+    // Consistent masks, but more buckets than the file has pages.
+    let mut data = data.clone();
+    for &(field, value) in &[(10, 0xffff_fffe), (11, u32::MAX), (12, 0x7fff_ffff)] {
+        data[4 * field..4 * field + 4].copy_from_slice(&value.to_be_bytes());
+    }
+    assert_eq!(HashedDatabase::parse(data).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_searcher() {
+    use std::io;
+    use std::path::PathBuf;
+    use terminfo::searcher::Searcher;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    let searcher = Searcher::new().default_dirs(vec!["tests/missing", "tests/tree"]);
+    assert_eq!(searcher.dirs(), paths(&["tests/missing", "tests/tree"]));
+    let lookup = searcher.lookup("xterm");
+    assert_eq!(lookup.path, Some(PathBuf::from("tests/tree/78/xterm")));
+    assert_eq!(lookup.checked,
+               paths(&["tests/missing.db",
+                       "tests/missing/x/xterm",
+                       "tests/missing/78/xterm",
+                       "tests/tree.db",
+                       "tests/tree/x/xterm",
+                       "tests/tree/78/xterm"]));
+    assert_eq!(searcher.entry("dumb").unwrap(),
+               Terminfo::from_path("tests/data/dumb").unwrap());
+    assert_eq!(searcher.lookup("nonexistent").path, None);
+    assert_eq!(searcher.lookup("nonexistent").checked.len(), 6);
+    assert_eq!(searcher.entry("nonexistent").unwrap_err().kind(), io::ErrorKind::NotFound);
+
+    let searcher = searcher.home_dir("tests/home")
+                           .var("TERMINFO_DIRS", "tests/hashed/terminfo::tests/other");
+    assert_eq!(searcher.dirs(),
+               paths(&["tests/home/.terminfo",
+                       "tests/hashed/terminfo",
+                       "tests/missing",
+                       "tests/tree",
+                       "tests/other"]));
+    assert_eq!(searcher.find("xterm"), Some(PathBuf::from("tests/hashed/terminfo.db")));
+    assert_eq!(searcher.entry("xterm").unwrap(),
+               Terminfo::from_path("tests/data/xterm").unwrap());
+
+    // TERMINFO is the only location searched.
+    let searcher = searcher.var("TERMINFO", "tests/tree");
+    assert_eq!(searcher.dirs(), paths(&["tests/tree"]));
+    assert_eq!(searcher.find("linux"), None);
+    assert!(searcher.env(vec![("TERMINFO", "tests/hashed/terminfo.db")]).find("linux").is_some());
+}
+
+#[test]
+fn test_searcher_entries() {
+    use std::path::Path;
+    use terminfo::searcher::Searcher;
+
+    let entries = Searcher::new()
+                      .default_dirs(vec!["tests/tree", "tests/missing", "tests/hashed/terminfo"])
+                      .entries();
+    let names: Vec<_> = entries.iter().map(|entry| &entry.name[..]).collect();
+    assert_eq!(names,
+               ["dumb",
+                "linux",
+                "rxvt",
+                "screen",
+                "screen-256color",
+                "vt100",
+                "vt220",
+                "xterm",
+                "xterm-256color",
+                "xterm-direct"]);
+
+    // The directory tree comes first and hides the hashed database.
+    let xterm = &entries[7];
+    assert_eq!(xterm.path, Path::new("tests/tree/78/xterm"));
+    assert!(xterm.aliases.is_empty());
+    assert_eq!(xterm.description.as_deref(), Some("xterm terminal emulator (X Window System)"));
+    assert_eq!(entries[4].path, Path::new("tests/hashed/terminfo.db"));
+    assert_eq!(entries[4].description.as_deref(), Some("GNU Screen with 256 colors"));
+
+    // Each name has a file; the primary one is listed.
+    let vt100 = &entries[5];
+    assert_eq!(vt100.path, Path::new("tests/tree/v/vt100"));
+    assert_eq!(vt100.aliases, ["vt100-am"]);
+    assert_eq!(vt100.description.as_deref(), Some("DEC VT100 (w/advanced video)"));
+}
+
+#[test]
+fn test_alias_lookup() {
+    use std::path::Path;
+    use std::sync::Arc;
+    use terminfo::searcher::Searcher;
+
+    let searcher = Searcher::new().default_dirs(vec!["tests/tree", "tests/hashed/terminfo"]);
+    // vt200 has no file of its own, so only the alias index finds it.
+    assert_eq!(searcher.find("vt200"), None);
+    assert_eq!(searcher.entry("vt200").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(searcher.entry("vt100-am").unwrap().names[0], "vt100");
+    assert_eq!(searcher.entry("nonexistent").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+    let aliases = searcher.aliases();
+    let vt220 = Terminfo::from_path("tests/tree/v/vt220").unwrap();
+    assert_eq!(aliases.get("vt200").unwrap().terminfo().unwrap(), vt220);
+    assert!(Arc::ptr_eq(&aliases, &searcher.clone().aliases()));
+    assert_eq!(aliases.get("vt200").unwrap().path, Path::new("tests/tree/v/vt220"));
+    assert_eq!(aliases.get("vt220").unwrap().name, "vt220");
+    assert_eq!(aliases.get("xterm-direct").unwrap().path, Path::new("tests/hashed/terminfo.db"));
+    assert_eq!(aliases.get("xterm").unwrap().path, Path::new("tests/tree/78/xterm"));
+    assert!(aliases.get("DEC VT220").is_none());
+    assert!(aliases.get("nonexistent").is_none());
+}
+
+#[test]
+fn test_source() {
+    use std::path::PathBuf;
+    use terminfo::Source;
+    use terminfo::parser::hashed::HashedDatabase;
+
+    let info = Terminfo::from_path("tests/data/linux").unwrap();
+    assert_eq!(*info.source(), Source::File(PathBuf::from("tests/data/linux")));
+    let db = HashedDatabase::open("tests/hashed/terminfo.db").unwrap();
+    let hashed = db.entry("linux").unwrap().unwrap();
+    assert_eq!(*hashed.source(), Source::HashedDatabase(PathBuf::from("tests/hashed/terminfo.db")));
+    assert_eq!(hashed, info);
+    let parsed = parse(&mut &fs::read("tests/data/linux").unwrap()[..]).unwrap();
+    assert_eq!(*parsed.source(), Source::Memory);
+    assert_eq!(*Terminfo::new(vec!["foo".into()]).source(), Source::Memory);
+}
+
+#[cfg(feature = "builtin")]
+#[test]
+fn test_builtin() {
+    use terminfo::{builtin, Source};
+
+    assert_eq!(builtin::names().count(), 10);
+    for name in builtin::names() {
+        let info = builtin::entry(name).unwrap();
+        assert_eq!(info.names[0], name);
+        assert_eq!(*info.source(), Source::Builtin);
+    }
+    assert_eq!(builtin::entry("vt100-am").unwrap().names[0], "vt100");
+    assert_eq!(builtin::entry("xterm-256color").unwrap().number("colors"), Some(256));
+    assert!(builtin::entry("nonexistent").is_none());
+}
+
+#[test]
+fn test_term_fallbacks() {
+    use std::io;
+    use terminfo::searcher::{fallbacks, Searcher};
+
+    assert_eq!(fallbacks("xterm-direct"), ["xterm-direct", "xterm", "dumb"]);
+    assert_eq!(fallbacks("dumb"), ["dumb"]);
+    assert_eq!(fallbacks(""), ["dumb"]);
+
+    let searcher = Searcher::new().default_dirs(vec!["tests/tree", "tests/hashed/terminfo"]);
+    let term_entry = |term: &str| {
+        let (name, info) = searcher.clone().var("TERM", term).term_entry().unwrap();
+        (name, info.names[0].clone())
+    };
+    assert_eq!(term_entry("xterm-direct"), ("xterm-direct".into(), "xterm-direct".into()));
+    assert_eq!(term_entry("xterm-kitty"), ("xterm-256color".into(), "xterm-256color".into()));
+    assert_eq!(term_entry("screen-256color-bce"),
+               ("screen-256color".into(), "screen-256color".into()));
+    assert_eq!(term_entry("vt100-am-w"), ("vt100-am".into(), "vt100".into()));
+    assert_eq!(term_entry("nonexistent"), ("dumb".into(), "dumb".into()));
+    assert_eq!(searcher.term_entry().unwrap().0, "dumb");
+
+    let empty = Searcher::new().default_dirs(Vec::<String>::new()).var("TERM", "xterm");
+    if cfg!(feature = "builtin") {
+        assert_eq!(empty.term_entry().unwrap().0, "xterm");
+    } else {
+        assert_eq!(empty.term_entry().unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
+
+#[test]
+fn test_padding() {
+    use terminfo::StringCap;
+    use terminfo::padding::Padding;
+    use terminfo::parm::{expand, Param, Variables};
+
+    let info = Terminfo::from_path("tests/tree/v/vt100").unwrap();
+    let cup = info.get(StringCap::CursorAddress).unwrap();
+    let cup = expand(cup, &[Param::Number(0), Param::Number(0)], &mut Variables::new()).unwrap();
+    assert_eq!(cup, b"\x1b[1;1H$<5>");
+
+    // vt100 uses XON/XOFF, so its delays aren't needed.
+    let padding = Padding::new(&info, 9600);
+    assert!(padding.xon_xoff);
+    let mut out = Vec::new();
+    padding.write(&cup, 1, &mut out).unwrap();
+    assert_eq!(out, b"\x1b[1;1H");
+
+    let padding = Padding { xon_xoff: false, padding_baud_rate: Some(0), ..padding };
+    let mut out = Vec::new();
+    padding.write(b"$<10/>", 1, &mut out).unwrap();
+    assert_eq!(out, [0; 10]);
+}
+
+#[test]
+fn test_program() {
+    use terminfo::parm::{expand, Param, Program, Variables};
+
+    let params = [[Param::Number(0), Param::Number(0), Param::Number(0)],
+                  [Param::Number(3), Param::Number(17), Param::Number(1)],
+                  [Param::Number(200), Param::Number(1000), Param::Number(65535)]];
+    for f in fs::read_dir("tests/data/").unwrap() {
+        let info = Terminfo::from_path(f.unwrap().path()).unwrap();
+        for (name, cap) in info.strings() {
+            let program = match Program::compile(cap) {
+                Ok(program) => program,
+                Err(e) => {
+                    assert_eq!(expand(cap, &params[0], &mut Variables::new()), Err(e), "{}", name);
+                    continue;
+                }
+            };
+            for params in &params {
+                assert_eq!(program.expand(params, &mut Variables::new()),
+                           expand(cap, params, &mut Variables::new()),
+                           "{}",
+                           name);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_analyze() {
+    use terminfo::StringCap;
+    use terminfo::parm::{analyze, Param, ParamType, Variables};
+
+    let numbers = [Param::Number(3), Param::Number(17), Param::Number(1)];
+    for f in fs::read_dir("tests/data/").unwrap() {
+        let info = Terminfo::from_path(f.unwrap().path()).unwrap();
+        if let Some(cup) = info.get(StringCap::CursorAddress) {
+            let analysis = analyze(cup);
+            assert_eq!(analysis.arity, 2);
+            assert_eq!(analysis.params[..2], [ParamType::Number, ParamType::Number]);
+            assert!(analysis.accepts(&numbers[..2]));
+        }
+        for (name, cap) in info.strings() {
+            let analysis = analyze(cap);
+            // Expansions the analysis finds nothing wrong with succeed.
+            if analysis.errors.is_empty() && analysis.accepts(&numbers) {
+                assert!(terminfo::parm::expand(cap, &numbers, &mut Variables::new()).is_ok(),
+                        "{}",
+                        name);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_termcap() {
+    use std::ffi::OsStr;
+    use terminfo::{BoolCap, Source, StringCap};
+    use terminfo::parser::termcap;
+
+    let source = fs::read_to_string("tests/source/termcap").unwrap();
+    let entries = termcap::parse(&source).unwrap();
+    assert_eq!(entries.len(), 2);
+    let vt100 = &entries[0];
+    assert_eq!(vt100.names, ["vt100", "vt100-am", "DEC VT100 (w/advanced video)"]);
+    assert_eq!(vt100.get(StringCap::CursorAddress), Some(&b"\x1b[%i%p1%d;%p2%dH$<5/>"[..]));
+    assert_eq!(vt100.get(StringCap::ParmDownCursor), Some(&b"\x1b[%p1%dB"[..]));
+    assert_eq!(vt100.get(StringCap::CursorLeft), Some(&b"\x08"[..]));
+    assert!(vt100.get(StringCap::SetAttributes).is_none());
+    assert!(!entries[1].contains(BoolCap::AutoRightMargin));
+    assert_eq!(entries[1].get(StringCap::ClearScreen), vt100.get(StringCap::ClearScreen));
+
+    let absolute = fs::canonicalize("tests/source/termcap").unwrap();
+    let info = termcap::lookup("vt100-nam", Some(absolute.as_os_str())).unwrap();
+    assert_eq!(*info.source(), Source::Termcap(absolute.clone()));
+    assert_eq!(info, entries[1]);
+    // An entry in `$TERMCAP` itself is used if it is the one asked for.
+    let info = termcap::lookup("x", Some(OsStr::new("x|X:co#40:cl=\\E[2J:"))).unwrap();
+    assert_eq!(info.number("cols"), Some(40));
+    assert_eq!(*info.source(), Source::Memory);
+    assert!(termcap::lookup("nonexistent", Some(absolute.as_os_str())).is_err());
+}
+
+#[test]
+fn test_names() {
+    use terminfo::{BoolCap, NumberCap, StringCap};
+    use terminfo::parser::compiled::*;
+
+    assert_eq!(boolnames.len(), boolcodes.len());
+    assert_eq!(numnames.len(), numfnames.len());
+    assert_eq!(stringnames.len(), stringfnames.len());
+    assert_eq!(StringCap::CursorAddress.termcap(), "cm");
+    assert_eq!(StringCap::CursorAddress.long_name(), "cursor_address");
+    assert_eq!(NumberCap::from_long_name("max_colors"), Some(NumberCap::MaxColors));
+    assert_eq!(BoolCap::from_termcap("am"), Some(BoolCap::AutoRightMargin));
+    for (i, &name) in stringnames.iter().enumerate() {
+        assert_eq!(terminfo_from_long_name(stringfnames[i]), Some(name));
+        assert_eq!(long_name_from_terminfo(name), Some(stringfnames[i]));
+    }
+
+    assert_eq!(termcap_from_terminfo("cols"), Some("co"));
+    assert_eq!(long_name_from_terminfo("am"), Some("auto_right_margin"));
+    assert_eq!(terminfo_from_termcap("kb"), Some("kbs"));
+    assert_eq!(long_name_from_termcap("co"), Some("columns"));
+    assert_eq!(termcap_from_long_name("key_backspace"), Some("kb"));
+    assert_eq!(terminfo_from_termcap("MT"), Some("OTMT"));
+    assert_eq!(terminfo_from_termcap("ML"), Some("smglr"));
+    assert_eq!(terminfo_from_termcap("cup"), None);
+    assert_eq!(termcap_from_terminfo("Tc"), None);
+}
+
+#[test]
+fn test_describe() {
+    use terminfo::{Category, StringCap, Value};
+    use terminfo::parser::compiled::category;
+
+    let info = Terminfo::from_path("tests/data/xterm").unwrap();
+    let described: Vec<_> = info.describe().collect();
+    // Extended capabilities aren't described.
+    let standard = info.bools()
+                       .chain(info.numbers().map(|(name, _)| name))
+                       .chain(info.strings().map(|(name, _)| name))
+                       .filter(|name| category(name).is_some());
+    assert_eq!(described.len(), standard.count());
+    assert!(described.contains(&("am", "auto_right_margin", "terminal has automatic margins",
+                                 Value::Bool)));
+    assert!(described.contains(&("colors", "max_colors", "maximum number of colors on screen",
+                                 Value::Number(8))));
+    let &(_, long_name, description, value) =
+        described.iter().find(|d| d.0 == "cup").unwrap();
+    assert_eq!((long_name, description), ("cursor_address", "move to row #1 columns #2"));
+    assert_eq!(value, Value::String(info.get(StringCap::CursorAddress).unwrap()));
+
+    assert_eq!(StringCap::SetAForeground.category(), Category::Colors);
+    assert_eq!(category("kf1"), Some(Category::Keys));
+    assert_eq!(category("cup"), Some(Category::Cursor));
+    assert_eq!(category("Tc"), None);
+    assert_eq!(Category::Editing.to_string(), "clearing, insertion and deletion");
+}
+
+#[test]
+fn test_parse_errors() {
+    use std::io;
+    use terminfo::{Error, ErrorKind, Section, TerminfoRef};
+
+    fn error(buf: &[u8]) -> (io::ErrorKind, Error) {
+        let e = TerminfoRef::parse(buf).unwrap_err();
+        (e.kind(), e.into_inner().unwrap().downcast::<Error>().map(|e| *e).unwrap())
+    }
+    let error_at = |offset, section, kind| Error { offset, section, kind };
+
+    // The header is followed by 24 bytes of names, 2 booleans, 1 number, 130 string offsets and
+    // an 8 byte string table.
+    let dumb = fs::read("tests/data/dumb").unwrap();
+    assert_eq!(error(&dumb[..100]),
+               (io::ErrorKind::UnexpectedEof,
+                error_at(40, Section::StringOffsets, ErrorKind::UnexpectedEof)));
+    assert_eq!(error(&[0x1a, 0x01, 0x05]).1,
+               error_at(2, Section::Header, ErrorKind::UnexpectedEof));
+    assert_eq!(error(&dumb[1..]).1, error_at(0, Section::Header, ErrorKind::BadMagic(0x1801)));
+
+    let mut buf = dumb.clone();
+    buf[4..6].copy_from_slice(&(-2i16).to_le_bytes());
+    assert_eq!(error(&buf),
+               (io::ErrorKind::InvalidData,
+                error_at(4, Section::Header, ErrorKind::InvalidLength("boolean count"))));
+
+    let mut buf = dumb.clone();
+    buf[20] = 0xff;
+    assert_eq!(error(&buf).1.offset, 20);
+    assert_eq!(error(&buf).1.section, Section::Names);
+
+    // The offset of `bel`, the second string, points past the string table.
+    let mut buf = dumb.clone();
+    buf[42..44].copy_from_slice(&100u16.to_le_bytes());
+    let e = error(&buf).1;
+    assert_eq!(e,
+               error_at(42,
+                        Section::StringOffsets,
+                        ErrorKind::InvalidStringOffset("bel".to_owned())));
+    assert_eq!(e.to_string(), "string offsets at byte 42: value of bel outside string table");
+
+    // A string table cut short leaves the last value, `ind`, without its NUL.
+    assert_eq!(error(&dumb[..dumb.len() - 1]).1,
+               error_at(306,
+                        Section::StringTable,
+                        ErrorKind::StringsMissingNull("ind".to_owned())));
+}